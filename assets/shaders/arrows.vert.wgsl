@@ -0,0 +1,39 @@
+struct CameraUniform {
+    view_proj: mat4x4<f32>,
+};
+@group(0) @binding(0)
+var<uniform> camera: CameraUniform;
+
+struct Input {
+    @location(0) position: vec2<f32>,
+};
+
+struct InstanceInput {
+    @location(1) model_matrix_0: vec4<f32>,
+    @location(2) model_matrix_1: vec4<f32>,
+    @location(3) model_matrix_2: vec4<f32>,
+    @location(4) model_matrix_3: vec4<f32>,
+    @location(5) color: vec4<f32>,
+};
+
+struct Output {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+};
+
+// Vertex shader
+@vertex
+fn vs_main(in: Input, instance: InstanceInput) -> Output {
+    let model_matrix = mat4x4<f32>(
+        instance.model_matrix_0,
+        instance.model_matrix_1,
+        instance.model_matrix_2,
+        instance.model_matrix_3,
+    );
+    var out: Output;
+    // Unit arrow -> World coords -> Device Coordinates
+    out.clip_position = camera.view_proj * model_matrix
+        * vec4<f32>(in.position, 0.0, 1.0);
+    out.color = instance.color;
+    return out;
+}
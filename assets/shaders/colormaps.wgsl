@@ -0,0 +1,86 @@
+// The original star palette, from white through blue, yellow and orange to red
+fn stellar(t: f32) -> vec3<f32> {
+    let brightest = vec3<f32>(0.97, 0.97, 1.0);
+    let blue = vec3<f32>(0.33, 0.4, 1.0);
+    let yellow = vec3<f32>(0.97, 0.98, 0.8);
+    let orange = vec3<f32>(0.96, 0.6, 0.25);
+    let red = vec3<f32>(0.99, 0.25, 0.25);
+
+    if (t >= 0.75) {
+        return mix(orange, red, (t - 0.75) * 4.0);
+    } else if (t >= 0.5) {
+        return mix(yellow, orange, (t - 0.5) * 4.0);
+    } else if (t >= 0.25) {
+        return mix(blue, yellow, (t - 0.25) * 4.0);
+    }
+    return mix(brightest, blue, t * 4.0);
+}
+
+// Scientific colormaps, sampled at nine evenly spaced stops and blended
+// linearly between them
+fn sample_stops(stops: array<vec3<f32>, 9>, t: f32) -> vec3<f32> {
+    var table = stops;
+    let x = t * 8.0;
+    let i = min(u32(floor(x)), 7u);
+    return mix(table[i], table[i + 1u], x - f32(i));
+}
+
+fn viridis(t: f32) -> vec3<f32> {
+    return sample_stops(array<vec3<f32>, 9>(
+        vec3<f32>(0.267, 0.005, 0.329),
+        vec3<f32>(0.279, 0.175, 0.483),
+        vec3<f32>(0.230, 0.322, 0.546),
+        vec3<f32>(0.173, 0.448, 0.558),
+        vec3<f32>(0.128, 0.567, 0.551),
+        vec3<f32>(0.158, 0.684, 0.502),
+        vec3<f32>(0.369, 0.789, 0.383),
+        vec3<f32>(0.678, 0.864, 0.190),
+        vec3<f32>(0.993, 0.906, 0.144),
+    ), t);
+}
+
+fn magma(t: f32) -> vec3<f32> {
+    return sample_stops(array<vec3<f32>, 9>(
+        vec3<f32>(0.001, 0.000, 0.014),
+        vec3<f32>(0.110, 0.063, 0.267),
+        vec3<f32>(0.310, 0.071, 0.483),
+        vec3<f32>(0.506, 0.145, 0.506),
+        vec3<f32>(0.711, 0.212, 0.478),
+        vec3<f32>(0.898, 0.314, 0.392),
+        vec3<f32>(0.984, 0.529, 0.381),
+        vec3<f32>(0.996, 0.761, 0.529),
+        vec3<f32>(0.987, 0.991, 0.749),
+    ), t);
+}
+
+fn cividis(t: f32) -> vec3<f32> {
+    return sample_stops(array<vec3<f32>, 9>(
+        vec3<f32>(0.000, 0.135, 0.305),
+        vec3<f32>(0.071, 0.208, 0.440),
+        vec3<f32>(0.232, 0.286, 0.424),
+        vec3<f32>(0.341, 0.365, 0.427),
+        vec3<f32>(0.440, 0.443, 0.451),
+        vec3<f32>(0.541, 0.525, 0.471),
+        vec3<f32>(0.647, 0.612, 0.455),
+        vec3<f32>(0.765, 0.702, 0.412),
+        vec3<f32>(0.996, 0.910, 0.220),
+    ), t);
+}
+
+// A colormap by its index in `Colormap`
+fn colormap(mode: u32, t: f32) -> vec3<f32> {
+    switch (mode) {
+        case 1u: {
+            return viridis(t);
+        }
+        case 2u: {
+            return magma(t);
+        }
+        case 3u: {
+            return cividis(t);
+        }
+        default: {
+            return stellar(t);
+        }
+    }
+}
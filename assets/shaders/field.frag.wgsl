@@ -0,0 +1,38 @@
+// Compiled with colormaps.wgsl in front, see `run` in lib.rs
+
+struct FieldUniform {
+    inv_view_proj: mat4x4<f32>,
+    body_count: u32,
+    bodies_width: u32,
+    resolution: u32,
+    quantity: u32,
+    softening_mode: u32,
+    softening_length: f32,
+    scale: f32,
+    colormap: u32,
+    contours: u32,
+};
+@group(0) @binding(0)
+var<uniform> field: FieldUniform;
+
+struct Input {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) shade: f32,
+};
+
+// Fragment shader
+@fragment
+fn fs_main(in: Input) -> @location(0) vec4<f32> {
+    var color = colormap(field.colormap, in.shade);
+
+    // Lines where the shade crosses evenly spaced levels, about a pixel wide
+    // at any zoom
+    if (field.contours > 0u) {
+        let level = in.shade * f32(field.contours);
+        let distance = abs(fract(level + 0.5) - 0.5) / fwidth(level);
+        let on_line = 1.0 - smoothstep(0.5, 1.5, distance);
+        color = mix(color, vec3<f32>(1.0), 0.4 * on_line);
+    }
+
+    return vec4<f32>(color, 1.0);
+}
@@ -0,0 +1,129 @@
+struct FieldUniform {
+    inv_view_proj: mat4x4<f32>,
+    body_count: u32,
+    bodies_width: u32,
+    resolution: u32,
+    quantity: u32,
+    softening_mode: u32,
+    softening_length: f32,
+    scale: f32,
+    colormap: u32,
+    contours: u32,
+};
+@group(0) @binding(0)
+var<uniform> field: FieldUniform;
+
+// One texel per body: position in xy and mu in z
+@group(0) @binding(1)
+var bodies: texture_2d<f32>;
+
+struct Output {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) shade: f32,
+};
+
+// The kernels below follow `Softening` in softening.rs
+
+fn spline_force(distance: f32, length: f32) -> f32 {
+    let u = distance / length;
+    let h3_inv = 1.0 / (length * length * length);
+    if (u < 0.5) {
+        return h3_inv * (32.0 / 3.0 + u * u * (32.0 * u - 38.4));
+    } else if (u < 1.0) {
+        return h3_inv * (64.0 / 3.0 - 48.0 * u + 38.4 * u * u
+            - 32.0 / 3.0 * u * u * u
+            - 1.0 / 15.0 / (u * u * u));
+    }
+    return 1.0 / (distance * distance * distance);
+}
+
+fn spline_potential(distance: f32, length: f32) -> f32 {
+    let u = distance / length;
+    if (u < 0.5) {
+        return (-2.8 + u * u * (16.0 / 3.0 + u * u * (6.4 * u - 9.6))) / length;
+    } else if (u < 1.0) {
+        return (-3.2
+            + 1.0 / 15.0 / u
+            + u * u * (32.0 / 3.0 + u * (-16.0 + u * (9.6 - 32.0 / 15.0 * u))))
+            / length;
+    }
+    return -1.0 / distance;
+}
+
+fn acceleration(dir: vec2<f32>, mu: f32) -> vec2<f32> {
+    let mag_2 = dot(dir, dir);
+    let soft_length = field.softening_length;
+    switch (field.softening_mode) {
+        case 1u: {
+            let soft_2 = mag_2 + soft_length * soft_length;
+            return dir * mu / (soft_2 * sqrt(soft_2));
+        }
+        case 2u: {
+            return dir * mu * spline_force(sqrt(mag_2), soft_length);
+        }
+        default: {
+            if (mag_2 == 0.0) {
+                return vec2<f32>(0.0);
+            }
+            return dir * mu / (mag_2 * sqrt(mag_2));
+        }
+    }
+}
+
+fn potential(distance: f32, mu: f32) -> f32 {
+    let soft_length = field.softening_length;
+    switch (field.softening_mode) {
+        case 1u: {
+            return -mu / sqrt(distance * distance + soft_length * soft_length);
+        }
+        case 2u: {
+            return mu * spline_potential(distance, soft_length);
+        }
+        default: {
+            if (distance == 0.0) {
+                return 0.0;
+            }
+            return -mu / distance;
+        }
+    }
+}
+
+// Vertex shader
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> Output {
+    // One vertex per grid corner, shared by the cells around it through the
+    // index buffer, see `FieldUniform::indices`
+    let corners = field.resolution + 1u;
+    let corner = vec2<u32>(index % corners, index / corners);
+    let ndc = vec2<f32>(corner) / f32(field.resolution) * 2.0 - 1.0;
+
+    // Device Coordinates -> World coords
+    let world = field.inv_view_proj * vec4<f32>(ndc, 0.0, 1.0);
+    let position = world.xy / world.w;
+
+    var total_potential: f32 = 0.0;
+    var total_acceleration: vec2<f32> = vec2<f32>(0.0);
+    for (var i: u32 = 0u; i < field.body_count; i = i + 1u) {
+        let texel = vec2<i32>(
+            i32(i % field.bodies_width),
+            i32(i / field.bodies_width),
+        );
+        let body = textureLoad(bodies, texel, 0);
+        let dir = body.xy - position;
+        if (field.quantity == 0u) {
+            total_potential += potential(length(dir), body.z);
+        } else {
+            total_acceleration += acceleration(dir, body.z);
+        }
+    }
+    var value = total_potential;
+    if (field.quantity != 0u) {
+        value = length(total_acceleration);
+    }
+
+    var out: Output;
+    out.clip_position = vec4<f32>(ndc, 0.0, 1.0);
+    // Same as `FieldOverlay::shade`
+    out.shade = abs(value) / (abs(value) + field.scale);
+    return out;
+}
@@ -1,3 +1,5 @@
+// Compiled with colormaps.wgsl in front, see `run` in lib.rs
+
 struct CameraUniform {
     view_proj: mat4x4<f32>,
 };
@@ -20,14 +22,17 @@ struct InstanceInput {
     @location(3) model_matrix_1: vec4<f32>,
     @location(4) model_matrix_2: vec4<f32>,
     @location(5) model_matrix_3: vec4<f32>,
-    @location(6) radius: f32,
+    @location(6) value: f32,
 };
 
 struct WorldUniform {
     radius: f32,
     boundary_segments: u32,
     rave: u32,
-    padding: u32,
+    boundary_mode: u32,
+    colormap: u32,
+    color_min: f32,
+    color_max: f32,
 };
 
 @group(2) @binding(0)
@@ -55,22 +60,11 @@ fn vs_main(
     // World coords -> Device Coordinates
     out.clip_position = camera.view_proj * world_vert;
 
-    var brightest: vec3<f32> = vec3<f32>(0.97, 0.97, 1.0);
-    var blue: vec3<f32> = vec3<f32>(0.33, 0.4, 1.0);
-    var yellow: vec3<f32> = vec3<f32>(0.97, 0.98, 0.8);
-    var orange: vec3<f32> = vec3<f32>(0.96, 0.6, 0.25);
-    var red: vec3<f32> = vec3<f32>(0.99, 0.25, 0.25);
+    // Map the colored attribute onto the range
+    let span = max(world.color_max - world.color_min, 1e-6);
+    let t = clamp((instance.value - world.color_min) / span, 0.0, 1.0);
 
-    var star_color: vec3<f32> = vec3<f32>(0.0);
-    if (instance.radius >= 0.75) {
-        star_color += mix(orange, red, (instance.radius - 0.75) * 4.0);
-    } else if (instance.radius >= 0.5) {
-        star_color += mix(yellow, orange, (instance.radius - 0.5) * 4.0);
-    } else if (instance.radius >= 0.25) {
-        star_color += mix(blue, yellow, (instance.radius - 0.25) * 4.0);
-    } else {
-        star_color += mix(brightest, blue, instance.radius * 4.0);
-    }
+    let star_color = colormap(world.colormap, t);
 
     var tint: vec3<f32> = vec3<f32>(1.0);
     if (world.rave > 0u) {
@@ -1,5 +1,19 @@
+struct WorldUniform {
+    radius: f32,
+    boundary_segments: u32,
+    rave: u32,
+    boundary_mode: u32,
+    colormap: u32,
+    color_min: f32,
+    color_max: f32,
+};
+
+@group(1) @binding(0)
+var<uniform> world: WorldUniform;
+
 struct Input {
     @builtin(position) clip_position: vec4<f32>,
+    @location(0) segment: f32,
 };
 
 struct Output {
@@ -10,6 +24,32 @@ struct Output {
 @fragment
 fn fs_main(in: Input) -> Output {
     var out: Output;
-    out.color = vec4<f32>(1.0);
+    // Dashed segments along the edge
+    var dash: bool = fract(in.segment / 4.0) < 0.5;
+
+    switch (world.boundary_mode) {
+        // Wall: solid
+        case 0u: {
+            out.color = vec4<f32>(1.0);
+        }
+        // Wrap: dashed blue
+        case 1u: {
+            if (!dash) {
+                discard;
+            }
+            out.color = vec4<f32>(0.33, 0.4, 1.0, 1.0);
+        }
+        // Despawn: solid red
+        case 2u: {
+            out.color = vec4<f32>(1.0, 0.3, 0.3, 1.0);
+        }
+        // Open: faint dashes
+        default: {
+            if (!dash) {
+                discard;
+            }
+            out.color = vec4<f32>(0.4, 0.4, 0.4, 1.0);
+        }
+    }
     return out;
-}
\ No newline at end of file
+}
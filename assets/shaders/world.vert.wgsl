@@ -8,7 +8,10 @@ struct WorldUniform {
     radius: f32,
     boundary_segments: u32,
     rave: u32,
-    padding: u32,
+    boundary_mode: u32,
+    colormap: u32,
+    color_min: f32,
+    color_max: f32,
 };
 
 @group(1) @binding(0)
@@ -16,6 +19,7 @@ var<uniform> world: WorldUniform;
 
 struct Output {
     @builtin(position) clip_position: vec4<f32>,
+    @location(0) segment: f32,
 };
 
 // Vertex shader
@@ -36,6 +40,7 @@ fn vs_main(
 
     // World coords -> Device Coordinates
     out.clip_position = camera.view_proj * world_vert;
+    out.segment = f32(i);
 
     return out;
 }
\ No newline at end of file
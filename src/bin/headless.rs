@@ -0,0 +1,300 @@
+//! Runs a simulation without a browser or GPU and writes the trajectory of
+//! every body, and optionally the system diagnostics, to CSV files.
+//!
+//! ```sh
+//! cargo run --release --no-default-features --features cli -- \
+//!     scenario.ron --steps 6000 --every 10 --out trajectories.csv
+//! ```
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::process::ExitCode;
+
+use nbody_wasm_sim::sim::{
+    BoundaryPolicy, CollisionMode, DensityModel, Diagnostics, GravitySolver,
+    Integration, IntegratorKind, Scenario, Simulation, SimulationConfig,
+    VelocityInit, BARNES_HUT_THETA,
+};
+
+const USAGE: &str = "\
+Usage: headless [SCENARIO] [OPTIONS]
+
+Loads SCENARIO (.ron or .json), or generates bodies when none is given.
+
+Options:
+  --steps <N>        Physics steps to run [default: 1000]
+  --every <N>        Write every Nth step [default: 1]
+  --out <PATH>       Output CSV [default: trajectories.csv]
+  --diagnostics <PATH>
+                     Also write energy and momentum to this CSV
+  --solver <NAME>    brute-force or barnes-hut [default: brute-force]
+  --boundary <NAME>  wall, wrap, despawn or open [default: from scenario]
+  --collisions <NAME>
+                     bounce or merge [default: from scenario]
+  --integrator <NAME>
+                     rapier, leapfrog, yoshida4 or rk4 [default: from scenario]
+  --preset <NAME>    Generate a built-in preset, e.g. figure-eight
+  --seed <N>         Seed for generated bodies [default: 0]
+  --bodies <N>       Number of generated bodies [default: 100]
+  --velocities <NAME>
+                     orbital or random, for generated bodies [default: orbital]
+  -h, --help         Print this message";
+
+struct Args {
+    scenario: Option<String>,
+    steps: u64,
+    every: u64,
+    out: String,
+    diagnostics: Option<String>,
+    solver: GravitySolver,
+    boundary: Option<BoundaryPolicy>,
+    collision_mode: Option<CollisionMode>,
+    integration: Option<Integration>,
+    config: SimulationConfig,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args {
+        scenario: None,
+        steps: 1000,
+        every: 1,
+        out: "trajectories.csv".to_owned(),
+        diagnostics: None,
+        solver: GravitySolver::BruteForce,
+        boundary: None,
+        collision_mode: None,
+        integration: None,
+        config: SimulationConfig::default(),
+    };
+
+    let mut iter = std::env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        let mut value =
+            || iter.next().ok_or_else(|| format!("{} needs a value", arg));
+        match arg.as_str() {
+            "--steps" => args.steps = parse(&value()?)?,
+            "--every" => args.every = parse::<u64>(&value()?)?.max(1),
+            "--out" => args.out = value()?,
+            "--diagnostics" => args.diagnostics = Some(value()?),
+            "--solver" => {
+                args.solver = match value()?.as_str() {
+                    "brute-force" => GravitySolver::BruteForce,
+                    "barnes-hut" => GravitySolver::BarnesHut {
+                        theta: BARNES_HUT_THETA,
+                    },
+                    other => return Err(format!("unknown solver {}", other)),
+                }
+            }
+            "--boundary" => {
+                args.boundary = Some(match value()?.as_str() {
+                    "wall" => BoundaryPolicy::default(),
+                    "wrap" => BoundaryPolicy::Wrap,
+                    "despawn" => BoundaryPolicy::Despawn,
+                    "open" => BoundaryPolicy::Open,
+                    other => return Err(format!("unknown boundary {}", other)),
+                })
+            }
+            "--collisions" => {
+                args.collision_mode = Some(match value()?.as_str() {
+                    "bounce" => CollisionMode::Bounce,
+                    "merge" => CollisionMode::Merge {
+                        density: DensityModel::default(),
+                    },
+                    other => {
+                        return Err(format!("unknown collisions {}", other))
+                    }
+                })
+            }
+            "--integrator" => {
+                args.integration = Some(match value()?.as_str() {
+                    "rapier" => Integration::Rapier,
+                    "leapfrog" => {
+                        Integration::Collisionless(IntegratorKind::Leapfrog)
+                    }
+                    "yoshida4" => {
+                        Integration::Collisionless(IntegratorKind::Yoshida4)
+                    }
+                    "rk4" => {
+                        Integration::Collisionless(IntegratorKind::RungeKutta4)
+                    }
+                    other => {
+                        return Err(format!("unknown integrator {}", other))
+                    }
+                })
+            }
+            "--preset" => args.config.preset = Some(value()?.parse()?),
+            "--seed" => args.config.seed = parse(&value()?)?,
+            "--bodies" => args.config.num_bodies = parse(&value()?)?,
+            "--velocities" => {
+                args.config.velocity = match value()?.as_str() {
+                    "orbital" => SimulationConfig::default().velocity,
+                    "random" => VelocityInit::Random,
+                    other => {
+                        return Err(format!("unknown velocities {}", other))
+                    }
+                }
+            }
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                std::process::exit(0);
+            }
+            _ if arg.starts_with('-') => {
+                return Err(format!("unknown option {}", arg))
+            }
+            _ => args.scenario = Some(arg),
+        }
+    }
+    Ok(args)
+}
+
+fn parse<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid number {}", value))
+}
+
+fn load_scenario(path: &str) -> Result<Scenario, String> {
+    let source = std::fs::read_to_string(path)
+        .map_err(|e| format!("{}: {}", path, e))?;
+    let scenario = match Path::new(path).extension().and_then(|e| e.to_str()) {
+        Some("json") => Scenario::from_json(&source),
+        _ => Scenario::from_ron(&source),
+    };
+    scenario.map_err(|e| format!("{}: {}", path, e))
+}
+
+fn create(path: &str) -> Result<BufWriter<File>, String> {
+    File::create(path)
+        .map(BufWriter::new)
+        .map_err(|e| format!("{}: {}", path, e))
+}
+
+fn write_trajectories(
+    out: &mut impl Write,
+    step: u64,
+    sim: &Simulation,
+) -> std::io::Result<()> {
+    let physics_context = &sim.physics_context;
+    for body in physics_context.bodies() {
+        let rigid_body = &physics_context.rigid_body_set[body.rigidbody_handle];
+        let position = body.position();
+        let velocity = rigid_body.linvel();
+        writeln!(
+            out,
+            "{},{},{},{},{},{},{},{},{}",
+            step,
+            physics_context.time,
+            body.id(),
+            position.x,
+            position.y,
+            velocity.x,
+            velocity.y,
+            body.rotation(),
+            rigid_body.angvel()
+        )?;
+    }
+    Ok(())
+}
+
+fn write_diagnostics(
+    out: &mut impl Write,
+    step: u64,
+    diagnostics: &Diagnostics,
+) -> std::io::Result<()> {
+    writeln!(
+        out,
+        "{},{},{},{},{},{},{},{},{},{}",
+        step,
+        diagnostics.time,
+        diagnostics.kinetic_energy,
+        diagnostics.potential_energy,
+        diagnostics.total_energy(),
+        diagnostics.momentum.x,
+        diagnostics.momentum.y,
+        diagnostics.angular_momentum,
+        diagnostics.center_of_mass.x,
+        diagnostics.center_of_mass.y
+    )
+}
+
+fn run(args: Args) -> Result<(), String> {
+    let scenario = match &args.scenario {
+        Some(path) => load_scenario(path)?,
+        None => args.config.generate(),
+    };
+    let mut sim =
+        Simulation::from_scenario(&scenario).map_err(|e| e.to_string())?;
+    // Nothing is drawn, so don't keep trails
+    sim.trails.enabled = false;
+    sim.physics_context.gravity_solver = args.solver;
+    if let Some(boundary) = args.boundary {
+        sim.physics_context.set_boundary(boundary);
+    }
+    if let Some(collision_mode) = args.collision_mode {
+        sim.physics_context.collision_mode = collision_mode;
+    }
+    if let Some(integration) = args.integration {
+        sim.physics_context.set_integration(integration);
+    }
+
+    let mut out = create(&args.out)?;
+    let out_error = |e: std::io::Error| format!("{}: {}", args.out, e);
+    writeln!(out, "step,time,body,x,y,vx,vy,rotation,angular_velocity")
+        .map_err(out_error)?;
+
+    let mut diagnostics_out = match &args.diagnostics {
+        Some(path) => Some(create(path)?),
+        None => None,
+    };
+    let diagnostics_error = |e: std::io::Error| {
+        format!("{}: {}", args.diagnostics.as_deref().unwrap_or_default(), e)
+    };
+    if let Some(out) = &mut diagnostics_out {
+        writeln!(
+            out,
+            "step,time,kinetic_energy,potential_energy,total_energy,\
+             momentum_x,momentum_y,angular_momentum,\
+             center_of_mass_x,center_of_mass_y"
+        )
+        .map_err(diagnostics_error)?;
+    }
+
+    for step in 0..=args.steps {
+        if step > 0 {
+            sim.step();
+        }
+        if step % args.every != 0 {
+            continue;
+        }
+
+        write_trajectories(&mut out, step, &sim).map_err(out_error)?;
+        if let Some(out) = &mut diagnostics_out {
+            let diagnostics = Diagnostics::measure(&sim.physics_context);
+            write_diagnostics(out, step, &diagnostics)
+                .map_err(diagnostics_error)?;
+        }
+    }
+
+    out.flush().map_err(out_error)?;
+    if let Some(out) = &mut diagnostics_out {
+        out.flush().map_err(diagnostics_error)?;
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -4,9 +4,14 @@ use wasm_bindgen::JsCast;
 use web_sys::HtmlCanvasElement;
 use winit::event::Event;
 
+use crate::sim::{DiagnosticsHistory, Inspection, Simulation};
+
 pub struct Dom {
     pub log_list: LogList,
     pub fps_counter: FpsCounter,
+    pub diagnostics_panel: DiagnosticsPanel,
+    pub inspector_panel: InspectorPanel,
+    pub clock: Clock,
 }
 
 impl Dom {
@@ -14,6 +19,9 @@ impl Dom {
         Self {
             log_list: get_log_list(),
             fps_counter: get_fps_counter(),
+            diagnostics_panel: get_diagnostics_panel(),
+            inspector_panel: get_inspector_panel(),
+            clock: get_clock(),
         }
     }
 }
@@ -26,6 +34,31 @@ pub fn get_canvas() -> HtmlCanvasElement {
         .expect("Canvas not found")
 }
 
+/// Reads a parameter from the page URL, e.g. `?seed=42`
+pub fn get_query_param(name: &str) -> Option<String> {
+    web_sys::window()
+        .and_then(|w| w.location().search().ok())
+        .and_then(|search| web_sys::UrlSearchParams::new_with_str(&search).ok())
+        .and_then(|params| params.get(name))
+}
+
+// Missing when the browser blocks storage, e.g. in some private modes
+fn get_local_storage() -> Option<web_sys::Storage> {
+    web_sys::window().and_then(|w| w.local_storage().ok().flatten())
+}
+
+/// Reads `key`, or nothing if it isn't set or storage is unavailable.
+pub fn load_from_storage(key: &str) -> Option<String> {
+    get_local_storage()?.get_item(key).ok().flatten()
+}
+
+/// Writes `key`, returning whether it worked. Fails when storage is full,
+/// e.g. past the browser's quota, or unavailable.
+pub fn save_to_storage(key: &str, value: &str) -> bool {
+    get_local_storage()
+        .is_some_and(|storage| storage.set_item(key, value).is_ok())
+}
+
 fn get_fps_counter() -> FpsCounter {
     FpsCounter {
         inner: web_sys::window()
@@ -37,6 +70,33 @@ fn get_fps_counter() -> FpsCounter {
     }
 }
 
+fn get_diagnostics_panel() -> DiagnosticsPanel {
+    DiagnosticsPanel {
+        inner: web_sys::window()
+            .and_then(|w| w.document())
+            .and_then(|d| d.get_element_by_id("d"))
+            .expect("Diagnostics panel not found"),
+    }
+}
+
+fn get_inspector_panel() -> InspectorPanel {
+    InspectorPanel {
+        inner: web_sys::window()
+            .and_then(|w| w.document())
+            .and_then(|d| d.get_element_by_id("p"))
+            .expect("Inspector panel not found"),
+    }
+}
+
+fn get_clock() -> Clock {
+    Clock {
+        inner: web_sys::window()
+            .and_then(|w| w.document())
+            .and_then(|d| d.get_element_by_id("t"))
+            .expect("Clock not found"),
+    }
+}
+
 fn get_log_list() -> LogList {
     LogList {
         inner: web_sys::window()
@@ -81,6 +141,89 @@ impl FpsCounter {
     }
 }
 
+pub struct DiagnosticsPanel {
+    inner: web_sys::Element,
+}
+
+impl DiagnosticsPanel {
+    pub fn update(&self, history: &DiagnosticsHistory) {
+        if let Some(latest) = history.latest() {
+            let label = format!(
+                "Energy: {:.4e} (drift {:+.2e})\n\
+                 Momentum: ({:.3e}, {:.3e})\n\
+                 Angular momentum: {:.4e}\n\
+                 Center of mass: ({:.2}, {:.2})",
+                latest.total_energy(),
+                history.energy_drift(),
+                latest.momentum.x,
+                latest.momentum.y,
+                latest.angular_momentum,
+                latest.center_of_mass.x,
+                latest.center_of_mass.y,
+            );
+            self.inner.set_text_content(Some(&label));
+        }
+    }
+}
+
+pub struct Clock {
+    inner: web_sys::Element,
+}
+
+impl Clock {
+    /// Shows simulated against wall clock time, and how time is running.
+    pub fn update(&self, sim: &Simulation) {
+        let mut label = format!(
+            "Sim time: {:.2}s, Wall time: {:.2}s, Speed: {}x",
+            sim.physics_context.time, sim.state.wall_time, sim.state.time_scale,
+        );
+        if sim.physics_context.is_reversed() {
+            label.push_str(", Reversed");
+        }
+        if sim.state.paused {
+            label.push_str(", Paused");
+        }
+        self.inner.set_text_content(Some(&label));
+    }
+}
+
+pub struct InspectorPanel {
+    inner: web_sys::Element,
+}
+
+impl InspectorPanel {
+    /// Shows the selected body, or hides the panel when there is none.
+    pub fn update(&self, inspection: Option<&Inspection>) {
+        let label = inspection.map(|body| {
+            let nearest = match body.nearest_neighbor {
+                Some((id, distance)) => format!("#{} at {:.2}", id.0, distance),
+                None => "none".to_owned(),
+            };
+            format!(
+                "Body #{}\n\
+                 Mass: {:.3}\n\
+                 Radius: {:.3}\n\
+                 Position: ({:.2}, {:.2})\n\
+                 Velocity: ({:.2}, {:.2})\n\
+                 Angular velocity: {:.3}\n\
+                 Kinetic energy: {:.4e}\n\
+                 Nearest: {}",
+                body.id.0,
+                body.mass,
+                body.radius,
+                body.position.x,
+                body.position.y,
+                body.velocity.x,
+                body.velocity.y,
+                body.angular_velocity,
+                body.kinetic_energy,
+                nearest,
+            )
+        });
+        self.inner.set_text_content(label.as_deref());
+    }
+}
+
 pub struct LogList {
     inner: web_sys::Element,
 }
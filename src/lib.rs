@@ -1,18 +1,29 @@
+#[cfg(feature = "web")]
 mod dom;
-mod render;
+pub mod render;
+#[cfg(feature = "web")]
 mod runtime;
-mod sim;
+pub mod sim;
 
+#[cfg(feature = "web")]
 use gloo_console::log;
+#[cfg(feature = "web")]
 use render::WgpuContext;
+#[cfg(feature = "web")]
 use wasm_bindgen::prelude::*;
+#[cfg(feature = "web")]
 use winit::dpi::LogicalSize;
+#[cfg(feature = "web")]
 use winit::platform::web::WindowBuilderExtWebSys;
+#[cfg(feature = "web")]
 use winit::{event_loop::EventLoop, window::WindowBuilder};
 
+#[cfg(feature = "web")]
 use crate::dom::Dom;
+#[cfg(feature = "web")]
 use crate::runtime::Runtime;
 
+#[cfg(feature = "web")]
 #[wasm_bindgen(start)]
 pub async fn run() {
     // Redirect panics to the console (debugging)
@@ -42,7 +53,13 @@ pub async fn run() {
     log!("Created window");
 
     // Load shaders
-    context.add_shader("vert", include_str!("../assets/shaders/vert.wgsl"));
+    context.add_shader(
+        "vert",
+        concat!(
+            include_str!("../assets/shaders/colormaps.wgsl"),
+            include_str!("../assets/shaders/vert.wgsl"),
+        ),
+    );
     context.add_shader("frag", include_str!("../assets/shaders/frag.wgsl"));
     context.add_shader(
         "wireframe.vert",
@@ -60,6 +77,29 @@ pub async fn run() {
         "world.frag",
         include_str!("../assets/shaders/world.frag.wgsl"),
     );
+    context.add_shader(
+        "lines.vert",
+        include_str!("../assets/shaders/lines.vert.wgsl"),
+    );
+    context.add_shader(
+        "lines.frag",
+        include_str!("../assets/shaders/lines.frag.wgsl"),
+    );
+    context.add_shader(
+        "arrows.vert",
+        include_str!("../assets/shaders/arrows.vert.wgsl"),
+    );
+    context.add_shader(
+        "field.vert",
+        include_str!("../assets/shaders/field.vert.wgsl"),
+    );
+    context.add_shader(
+        "field.frag",
+        concat!(
+            include_str!("../assets/shaders/colormaps.wgsl"),
+            include_str!("../assets/shaders/field.frag.wgsl"),
+        ),
+    );
     log!("Loaded shaders");
 
     // Load textures
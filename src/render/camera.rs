@@ -1,4 +1,4 @@
-use glam::{Mat4, Quat, Vec2};
+use glam::{Mat4, Quat, Vec2, Vec3Swizzles};
 
 pub struct Camera {
     scale: f32,
@@ -47,4 +47,73 @@ impl Camera {
 
         proj * view.inverse()
     }
+
+    /// The world position under a point on the screen, in pixels from the
+    /// top left corner.
+    pub fn screen_to_world(&self, screen: Vec2) -> Vec2 {
+        let ndc = Vec2::new(
+            2.0 * screen.x / self.view_size.x - 1.0,
+            1.0 - 2.0 * screen.y / self.view_size.y,
+        );
+        self.build_view_projection_matrix()
+            .inverse()
+            .project_point3(ndc.extend(0.0))
+            .xy()
+    }
+
+    /// The screen position of a world point, in pixels from the top left
+    /// corner.
+    pub fn world_to_screen(&self, world: Vec2) -> Vec2 {
+        let ndc = self
+            .build_view_projection_matrix()
+            .project_point3(world.extend(0.0));
+        Vec2::new(
+            (ndc.x + 1.0) / 2.0 * self.view_size.x,
+            (1.0 - ndc.y) / 2.0 * self.view_size.y,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec2;
+
+    use super::Camera;
+
+    #[test]
+    fn screen_center_is_the_pan() {
+        let camera = Camera::new(
+            Vec2::new(800.0, 600.0),
+            0.7,
+            Vec2::new(3.0, -4.0),
+            5.0,
+        );
+        let center = camera.screen_to_world(Vec2::new(400.0, 300.0));
+        assert!(center.distance(Vec2::new(3.0, -4.0)) < 1e-4, "{}", center);
+    }
+
+    #[test]
+    fn screen_y_points_down() {
+        let camera =
+            Camera::new(Vec2::new(800.0, 600.0), 0.0, Vec2::ZERO, 10.0);
+        // 100 pixels right and up of the center, at 10 pixels per unit
+        let world = camera.screen_to_world(Vec2::new(500.0, 200.0));
+        assert!(world.distance(Vec2::new(10.0, 10.0)) < 1e-4, "{}", world);
+    }
+
+    #[test]
+    fn unprojection_inverts_projection() {
+        let camera = Camera::new(
+            Vec2::new(1280.0, 720.0),
+            -2.1,
+            Vec2::new(-7.5, 2.0),
+            3.3,
+        );
+        for world in [Vec2::ZERO, Vec2::new(40.0, -12.0), Vec2::new(-3.0, 9.5)]
+        {
+            let screen = camera.world_to_screen(world);
+            let back = camera.screen_to_world(screen);
+            assert!(back.distance(world) < 1e-3, "{} != {}", back, world);
+        }
+    }
 }
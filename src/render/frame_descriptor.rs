@@ -1,46 +1,149 @@
-use glam::{Mat4, Quat, Vec2};
-use wgpu::{
-    util::DeviceExt, BindGroup, BindGroupLayout, Buffer, Color, Device,
-};
+use glam::{Mat4, Quat, Vec2, Vec4};
+use particular::prelude::Particle;
+use wgpu::Color;
 
 use crate::{
     render::camera::Camera,
     render::gpu_types::{
-        CameraUniform, GpuPrimitive, GpuQuad, GpuTransform, GpuUniform,
-        WorldUniform,
+        CameraUniform, FieldUniform, GpuArrow, GpuArrowVertex, GpuPrimitive,
+        GpuQuad, GpuTransform, WorldUniform,
     },
-    sim::Simulation,
+    render::lines::{LineBatch, LineStrips},
+    sim::{BoundaryPolicy, Coloring, Simulation},
 };
 
+// Size of the selection highlight, relative to the body
+const SELECTION_RING_SCALE: f32 = 1.3;
+const VELOCITY_COLOR: Vec4 = Vec4::new(0.4, 1.0, 0.4, 1.0);
+const ACCELERATION_COLOR: Vec4 = Vec4::new(1.0, 0.45, 0.2, 1.0);
+// Opacity of the newest end of a trail, fading to nothing at the oldest
+const TRAIL_ALPHA: f32 = 0.5;
+
 pub struct FrameDescriptor {
     wireframe: bool,
     rave: bool,
+    world_radius: f32,
+    boundary: BoundaryPolicy,
+    coloring: Coloring,
+    color_bounds: (f32, f32),
     transforms: Vec<GpuTransform>,
+    lines: LineBatch,
+    trails: LineStrips,
+    arrows: Vec<GpuArrow>,
+    field: Option<FieldUniform>,
+    field_bodies: Vec<[f32; 4]>,
     camera: Camera,
     pub clear_color: Color,
 }
 
 impl FrameDescriptor {
     pub fn build(sim: &Simulation) -> FrameDescriptor {
+        // Blend between the last two physics steps
+        let alpha = sim.timestep.alpha();
+
+        let coloring = sim.state.coloring;
+        let values = coloring.values(&sim.physics_context);
+        let color_bounds = coloring.bounds(&values);
+
         let mut transforms = Vec::new();
-        for body in sim.physics_context.bodies.iter() {
+        for (body, value) in sim.physics_context.bodies().zip(values) {
             transforms.push(GpuTransform {
                 model: Mat4::from_scale_rotation_translation(
                     Vec2::splat(2.0 * body.radius()).extend(1.0),
-                    Quat::from_rotation_z(body.rotation()),
-                    body.position().extend(1.0),
+                    Quat::from_rotation_z(body.interpolated_rotation(alpha)),
+                    body.interpolated_position(alpha).extend(1.0),
                 )
                 .to_cols_array_2d(),
-                radius: body.radius(),
+                value,
             })
         }
 
-        let camera = Camera::new(
-            sim.state.view_size.as_vec2(),
-            sim.state.rotation,
-            sim.state.pan,
-            sim.state.zoom,
-        );
+        let mut trails = LineStrips::default();
+        if sim.trails.enabled {
+            for body in sim.physics_context.bodies() {
+                let Some(trail) = sim.trails.get(body.id()) else {
+                    continue;
+                };
+                // The newest point is where the body is after the step, so
+                // it's swapped for where the body is drawn this frame
+                let head = body.interpolated_position(alpha);
+                let count = trail.len().max(1);
+                let points = trail.range(..count - 1).copied().chain([head]);
+                trails.strip(points.enumerate().map(|(age, position)| {
+                    let fade = (age + 1) as f32 / count as f32;
+                    (position, Vec4::new(1.0, 1.0, 1.0, TRAIL_ALPHA * fade))
+                }));
+            }
+        }
+
+        let mut arrows = Vec::new();
+        if sim.state.show_vectors {
+            let physics_context = &sim.physics_context;
+            for body in physics_context.bodies() {
+                let rb = &physics_context.rigid_body_set[body.rigidbody_handle];
+                let position = body.interpolated_position(alpha);
+                arrows.extend(GpuArrow::new(
+                    position,
+                    (*rb.linvel()).into(),
+                    VELOCITY_COLOR,
+                ));
+                arrows.extend(GpuArrow::new(
+                    position,
+                    body.acceleration(),
+                    ACCELERATION_COLOR,
+                ));
+            }
+        }
+
+        let mut lines = LineBatch::default();
+        // Highlight the selected body
+        if let Some(body) = sim
+            .state
+            .selected
+            .and_then(|id| sim.physics_context.body(id))
+        {
+            lines.ring(
+                body.interpolated_position(alpha),
+                body.radius() * SELECTION_RING_SCALE,
+                Vec4::new(1.0, 0.85, 0.2, 1.0),
+            );
+        }
+        // Preview of the body being launched
+        let slingshot = &sim.state.slingshot;
+        if let Some(anchor) = slingshot.anchor {
+            let cursor = sim.state.cursor_world();
+            let body = slingshot.aim(cursor).unwrap();
+            lines.ring(anchor, slingshot.radius, Vec4::ONE);
+            lines.line(anchor, cursor, Vec4::new(1.0, 1.0, 1.0, 0.3));
+            lines.arrow(anchor, anchor + body.velocity, VELOCITY_COLOR);
+        }
+
+        let camera = sim.state.camera();
+
+        // The field is drawn from where the bodies are, not where they
+        // appear between steps, to match the CPU reference
+        let overlay = &sim.state.field;
+        let (field, field_bodies) = match overlay.enabled {
+            true => {
+                let physics_context = &sim.physics_context;
+                let bodies = physics_context
+                    .bodies()
+                    .map(|body| {
+                        let position = body.position();
+                        [position.x, position.y, body.mu(), 0.0]
+                    })
+                    .collect::<Vec<_>>();
+                let uniform = FieldUniform::new(
+                    &camera,
+                    overlay,
+                    physics_context.softening,
+                    overlay.scale(physics_context),
+                    bodies.len(),
+                );
+                (Some(uniform), bodies)
+            }
+            false => (None, Vec::new()),
+        };
 
         let clear_color = Color {
             r: sim.state.bg_color.x,
@@ -52,64 +155,72 @@ impl FrameDescriptor {
         FrameDescriptor {
             wireframe: sim.state.wireframe,
             rave: sim.state.rave,
+            world_radius: sim.physics_context.world_radius,
+            boundary: sim.physics_context.boundary(),
+            coloring,
+            color_bounds,
             transforms,
+            lines,
+            trails,
+            arrows,
+            field,
+            field_bodies,
             camera,
             clear_color,
         }
     }
 
     pub fn indicies(&self) -> Vec<u16> {
-        match self.wireframe {
-            true => vec![0, 1, 2, 3, 0],
-            false => vec![0, 1, 2, 0, 2, 3],
-        }
+        GpuQuad::indices(self.wireframe)
     }
 
     pub fn instances(&self) -> &Vec<GpuTransform> {
         &self.transforms
     }
 
-    pub fn create_vertex_buffer(&self, device: &Device) -> Buffer {
-        device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Vertex Buffer"),
-            contents: &GpuQuad.data(),
-            usage: wgpu::BufferUsages::VERTEX,
-        })
+    pub fn instance_data(&self) -> Vec<u8> {
+        self.transforms
+            .iter()
+            .flat_map(GpuTransform::data)
+            .collect()
     }
 
-    pub fn create_index_buffer(&self, device: &Device) -> Buffer {
-        device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Index Buffer"),
-            contents: bytemuck::cast_slice(&self.indicies()),
-            usage: wgpu::BufferUsages::INDEX,
-        })
+    pub fn lines(&self) -> &LineBatch {
+        &self.lines
     }
 
-    pub fn create_instance_buffer(&self, device: &Device) -> Buffer {
-        let instance_data = self
-            .transforms
-            .iter()
-            .flat_map(GpuTransform::data)
-            .collect::<Vec<_>>();
+    pub fn trails(&self) -> &LineStrips {
+        &self.trails
+    }
+
+    pub fn arrows(&self) -> &[GpuArrow] {
+        &self.arrows
+    }
+
+    /// The field overlay, when it is shown.
+    pub fn field_uniform(&self) -> Option<&FieldUniform> {
+        self.field.as_ref()
+    }
+
+    pub fn field_bodies(&self) -> &[[f32; 4]] {
+        &self.field_bodies
+    }
 
-        device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Instance Buffer"),
-            contents: bytemuck::cast_slice(&instance_data),
-            usage: wgpu::BufferUsages::VERTEX,
-        })
+    pub fn arrow_mesh_len(&self) -> u32 {
+        GpuArrowVertex::MESH.len() as u32
     }
 
-    pub fn create_camera_binding(
-        &self,
-        device: &Device,
-    ) -> (Buffer, Vec<u8>, BindGroup, BindGroupLayout) {
-        CameraUniform::from(&self.camera).bind(device)
+    pub fn camera_uniform(&self) -> CameraUniform<'_> {
+        CameraUniform::from(&self.camera)
     }
 
-    pub fn create_world_data_binding(
-        &self,
-        device: &Device,
-    ) -> (Buffer, Vec<u8>, BindGroup, BindGroupLayout) {
-        WorldUniform::from(self.rave).bind(device)
+    pub fn world_uniform(&self) -> WorldUniform {
+        WorldUniform::new(
+            self.world_radius,
+            self.boundary,
+            self.rave,
+            &self.coloring,
+            self.color_bounds,
+        )
     }
 }
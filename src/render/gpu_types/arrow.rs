@@ -0,0 +1,117 @@
+use std::mem;
+
+use glam::{Mat4, Quat, Vec2, Vec3, Vec4};
+use wgpu::VertexBufferLayout;
+
+use crate::render::gpu_types::GpuPrimitive;
+
+// World units of arrow per e-fold of the vector's magnitude
+const ARROW_LOG_SCALE: f32 = 2.0;
+
+/// A point on the unit arrow mesh, which points along +x from the origin.
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct GpuArrowVertex {
+    pub position: [f32; 2],
+}
+
+unsafe impl bytemuck::Pod for GpuArrowVertex {}
+unsafe impl bytemuck::Zeroable for GpuArrowVertex {}
+
+impl GpuArrowVertex {
+    /// Shaft and both sides of the head, as a line list.
+    pub const MESH: [GpuArrowVertex; 6] = [
+        GpuArrowVertex {
+            position: [0.0, 0.0],
+        },
+        GpuArrowVertex {
+            position: [1.0, 0.0],
+        },
+        GpuArrowVertex {
+            position: [1.0, 0.0],
+        },
+        GpuArrowVertex {
+            position: [0.77, 0.1],
+        },
+        GpuArrowVertex {
+            position: [1.0, 0.0],
+        },
+        GpuArrowVertex {
+            position: [0.77, -0.1],
+        },
+    ];
+
+    pub const BUFFER_LAYOUT: VertexBufferLayout<'static> =
+        wgpu::VertexBufferLayout {
+            array_stride: mem::size_of::<GpuArrowVertex>()
+                as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &wgpu::vertex_attr_array![0 => Float32x2],
+        };
+}
+
+impl GpuPrimitive for GpuArrowVertex {
+    fn data(&self) -> Vec<u8> {
+        bytemuck::cast_slice(&[*self]).to_vec()
+    }
+
+    fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        Self::BUFFER_LAYOUT
+    }
+}
+
+/// Places, turns and sizes one instance of the arrow mesh.
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct GpuArrow {
+    pub model: [[f32; 4]; 4],
+    pub color: [f32; 4],
+}
+
+unsafe impl bytemuck::Pod for GpuArrow {}
+unsafe impl bytemuck::Zeroable for GpuArrow {}
+
+impl GpuArrow {
+    pub const BUFFER_LAYOUT: VertexBufferLayout<'static> =
+        wgpu::VertexBufferLayout {
+            array_stride: mem::size_of::<GpuArrow>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &wgpu::vertex_attr_array![
+                1 => Float32x4,
+                2 => Float32x4,
+                3 => Float32x4,
+                4 => Float32x4,
+                5 => Float32x4,
+            ],
+        };
+
+    /// An arrow from `origin` along `vector`, with a length that grows with
+    /// the log of its magnitude so small and huge vectors both stay legible.
+    /// None for a zero vector, which has no direction.
+    pub fn new(origin: Vec2, vector: Vec2, color: Vec4) -> Option<Self> {
+        let magnitude = vector.length();
+        if magnitude == 0.0 || !magnitude.is_finite() {
+            return None;
+        }
+        let length = ARROW_LOG_SCALE * magnitude.ln_1p();
+        let model = Mat4::from_scale_rotation_translation(
+            Vec3::splat(length),
+            Quat::from_rotation_z(vector.y.atan2(vector.x)),
+            origin.extend(0.0),
+        );
+        Some(Self {
+            model: model.to_cols_array_2d(),
+            color: color.into(),
+        })
+    }
+}
+
+impl GpuPrimitive for GpuArrow {
+    fn data(&self) -> Vec<u8> {
+        bytemuck::cast_slice(&[*self]).to_vec()
+    }
+
+    fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        Self::BUFFER_LAYOUT
+    }
+}
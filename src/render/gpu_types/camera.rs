@@ -13,12 +13,20 @@ impl<'a> From<&'a Camera> for CameraUniform<'a> {
 }
 
 impl<'a> GpuUniform for CameraUniform<'a> {
+    fn contents(&self) -> Vec<u8> {
+        let matrix = self
+            .camera
+            .build_view_projection_matrix()
+            .to_cols_array_2d();
+        bytemuck::cast_slice(&[matrix]).to_vec()
+    }
+
     fn bind(
         &self,
         device: &Device,
     ) -> (Buffer, Vec<u8>, BindGroup, BindGroupLayout) {
         let layout = self.create_bind_group_layout(device);
-        let buffer_contents = self.get_buffer_contents();
+        let buffer_contents = self.contents();
         let buffer = self.create_buffer(device, &buffer_contents);
         let bind_group = self.create_bind_group(&buffer, &layout, device);
         (buffer, buffer_contents, bind_group, layout)
@@ -26,14 +34,6 @@ impl<'a> GpuUniform for CameraUniform<'a> {
 }
 
 impl<'a> CameraUniform<'a> {
-    fn get_buffer_contents(&self) -> Vec<u8> {
-        let matrix = self
-            .camera
-            .build_view_projection_matrix()
-            .to_cols_array_2d();
-        bytemuck::cast_slice(&[matrix]).to_vec()
-    }
-
     fn create_bind_group(
         &self,
         buffer: &Buffer,
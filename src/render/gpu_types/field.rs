@@ -0,0 +1,73 @@
+use crate::{
+    render::Camera,
+    sim::{FieldOverlay, Softening},
+};
+
+/// Bodies per row of the texture the field shader reads them from.
+pub const FIELD_TEXTURE_WIDTH: u32 = 256;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct FieldUniform {
+    /// Device coordinates back to the world
+    pub inv_view_proj: [[f32; 4]; 4],
+    pub body_count: u32,
+    pub bodies_width: u32,
+    pub resolution: u32,
+    pub quantity: u32,
+    pub softening_mode: u32,
+    pub softening_length: f32,
+    /// Field magnitude shaded halfway along the colormap
+    pub scale: f32,
+    pub colormap: u32,
+    pub contours: u32,
+    _padding: [u32; 3],
+}
+
+impl FieldUniform {
+    pub fn new(
+        camera: &Camera,
+        overlay: &FieldOverlay,
+        softening: Softening,
+        scale: f32,
+        body_count: usize,
+    ) -> Self {
+        Self {
+            inv_view_proj: camera
+                .build_view_projection_matrix()
+                .inverse()
+                .to_cols_array_2d(),
+            body_count: body_count as u32,
+            bodies_width: FIELD_TEXTURE_WIDTH,
+            resolution: overlay.resolution(),
+            quantity: overlay.quantity.shader_mode(),
+            softening_mode: softening.shader_mode(),
+            softening_length: softening.length(),
+            scale,
+            colormap: overlay.colormap.shader_mode(),
+            contours: overlay.contour_levels(),
+            _padding: [0; 3],
+        }
+    }
+
+    /// Two triangles for every cell of the grid, sharing the corners so each
+    /// corner sums over the bodies once rather than for every cell around it.
+    /// Corners are numbered a row at a time.
+    pub fn indices(&self) -> Vec<u32> {
+        let corners = self.resolution + 1;
+        (0..self.resolution)
+            .flat_map(|y| (0..self.resolution).map(move |x| y * corners + x))
+            .flat_map(|corner| {
+                let (right, up) = (corner + 1, corner + corners);
+                [corner, right, up, up, right, up + 1]
+            })
+            .collect()
+    }
+
+    pub fn index_count(&self) -> u32 {
+        6 * self.resolution * self.resolution
+    }
+}
+
+unsafe impl bytemuck::Pod for FieldUniform {}
+unsafe impl bytemuck::Zeroable for FieldUniform {}
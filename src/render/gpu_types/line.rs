@@ -0,0 +1,34 @@
+use wgpu::VertexBufferLayout;
+
+use crate::render::gpu_types::GpuPrimitive;
+
+/// One end of a line segment, in world space.
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct GpuLineVertex {
+    pub position: [f32; 2],
+    pub color: [f32; 4],
+}
+
+unsafe impl bytemuck::Pod for GpuLineVertex {}
+unsafe impl bytemuck::Zeroable for GpuLineVertex {}
+
+impl GpuLineVertex {
+    pub const BUFFER_LAYOUT: VertexBufferLayout<'static> =
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<GpuLineVertex>()
+                as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &wgpu::vertex_attr_array![0 => Float32x2, 1 => Float32x4],
+        };
+}
+
+impl GpuPrimitive for GpuLineVertex {
+    fn data(&self) -> Vec<u8> {
+        bytemuck::cast_slice(&[*self]).to_vec()
+    }
+
+    fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        Self::BUFFER_LAYOUT
+    }
+}
@@ -6,6 +6,8 @@ pub trait GpuPrimitive {
 }
 
 pub trait GpuUniform {
+    /// The bytes to upload to the uniform buffer.
+    fn contents(&self) -> Vec<u8>;
     fn bind(
         &self,
         device: &Device,
@@ -26,3 +28,12 @@ pub use camera::CameraUniform;
 
 mod world;
 pub use world::WorldUniform;
+
+mod line;
+pub use line::GpuLineVertex;
+
+mod arrow;
+pub use arrow::{GpuArrow, GpuArrowVertex};
+
+mod field;
+pub use field::{FieldUniform, FIELD_TEXTURE_WIDTH};
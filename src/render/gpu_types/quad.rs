@@ -32,5 +32,15 @@ impl GpuPrimitive for GpuQuad {
     }
 }
 
+impl GpuQuad {
+    /// Two triangles, or the outline when drawn as a line strip.
+    pub fn indices(wireframe: bool) -> Vec<u16> {
+        match wireframe {
+            true => vec![0, 1, 2, 3, 0],
+            false => vec![0, 1, 2, 0, 2, 3],
+        }
+    }
+}
+
 unsafe impl bytemuck::Pod for GpuQuad {}
 unsafe impl bytemuck::Zeroable for GpuQuad {}
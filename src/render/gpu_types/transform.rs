@@ -7,7 +7,8 @@ use crate::render::gpu_types::GpuPrimitive;
 #[derive(Copy, Clone)]
 pub struct GpuTransform {
     pub model: [[f32; 4]; 4],
-    pub radius: f32,
+    /// The attribute bodies are colored by, before mapping to the range
+    pub value: f32,
 }
 
 unsafe impl bytemuck::Pod for GpuTransform {}
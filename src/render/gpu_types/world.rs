@@ -2,7 +2,7 @@ use wgpu::{util::DeviceExt, BindGroup, BindGroupLayout, Buffer, Device};
 
 use crate::{
     render::gpu_types::GpuUniform,
-    sim::{WORLD_EDGE_SEGMENTS, WORLD_RADIUS},
+    sim::{BoundaryPolicy, Coloring, WORLD_EDGE_SEGMENTS},
 };
 
 #[repr(C)]
@@ -11,16 +11,31 @@ pub struct WorldUniform {
     pub radius: f32,
     pub boundary_segments: u32,
     pub rave_mode: u32,
+    pub boundary_mode: u32,
+    pub colormap: u32,
+    /// Values mapped to either end of the colormap
+    pub color_min: f32,
+    pub color_max: f32,
     _padding: u32,
 }
 
-impl From<bool> for WorldUniform {
-    fn from(rave: bool) -> Self {
+impl WorldUniform {
+    pub fn new(
+        radius: f32,
+        boundary: BoundaryPolicy,
+        rave: bool,
+        coloring: &Coloring,
+        (color_min, color_max): (f32, f32),
+    ) -> Self {
         Self {
-            radius: WORLD_RADIUS,
+            radius,
             boundary_segments: WORLD_EDGE_SEGMENTS,
             rave_mode: rave as u32,
-            _padding: 0_u32,
+            boundary_mode: boundary.shader_mode(),
+            colormap: coloring.colormap.shader_mode(),
+            color_min,
+            color_max,
+            _padding: 0,
         }
     }
 }
@@ -29,28 +44,22 @@ unsafe impl bytemuck::Pod for WorldUniform {}
 unsafe impl bytemuck::Zeroable for WorldUniform {}
 
 impl GpuUniform for WorldUniform {
+    fn contents(&self) -> Vec<u8> {
+        bytemuck::cast_slice(&[*self]).to_vec()
+    }
+
     fn bind(
         &self,
         device: &Device,
     ) -> (Buffer, Vec<u8>, BindGroup, BindGroupLayout) {
         let layout = create_world_bind_group_layout(device);
-        let buffer_contents = get_world_buffer_contents(self.rave_mode);
+        let buffer_contents = self.contents();
         let buffer = create_world_buffer(device, &buffer_contents);
         let bind_group = create_world_bind_group(&buffer, &layout, device);
         (buffer, buffer_contents, bind_group, layout)
     }
 }
 
-fn get_world_buffer_contents(rave: u32) -> Vec<u8> {
-    let uniform = WorldUniform {
-        radius: WORLD_RADIUS,
-        boundary_segments: WORLD_EDGE_SEGMENTS,
-        rave_mode: rave,
-        _padding: 0_u32,
-    };
-    bytemuck::cast_slice(&[uniform]).to_vec()
-}
-
 fn create_world_bind_group(
     buffer: &Buffer,
     layout: &BindGroupLayout,
@@ -80,7 +89,7 @@ fn create_world_bind_group_layout(device: &Device) -> BindGroupLayout {
     device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
         entries: &[wgpu::BindGroupLayoutEntry {
             binding: 0,
-            visibility: wgpu::ShaderStages::VERTEX,
+            visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
             ty: wgpu::BindingType::Buffer {
                 ty: wgpu::BufferBindingType::Uniform,
                 has_dynamic_offset: false,
@@ -0,0 +1,95 @@
+use std::f32::consts::PI;
+
+use glam::{Vec2, Vec4};
+
+use crate::render::gpu_types::GpuLineVertex;
+
+const RING_SEGMENTS: u32 = 48;
+const ARROW_HEAD_ANGLE: f32 = 0.4;
+
+/// Overlay lines for one frame, drawn as a line list.
+#[derive(Default)]
+pub struct LineBatch {
+    vertices: Vec<GpuLineVertex>,
+}
+
+impl LineBatch {
+    pub fn vertices(&self) -> &[GpuLineVertex] {
+        &self.vertices
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vertices.is_empty()
+    }
+
+    pub fn line(&mut self, from: Vec2, to: Vec2, color: Vec4) {
+        for position in [from, to] {
+            self.vertices.push(GpuLineVertex {
+                position: position.into(),
+                color: color.into(),
+            });
+        }
+    }
+
+    pub fn ring(&mut self, center: Vec2, radius: f32, color: Vec4) {
+        let delta_radians = 2.0 * PI / RING_SEGMENTS as f32;
+        for i in 0..RING_SEGMENTS {
+            let from = Vec2::from_angle(delta_radians * i as f32);
+            let to = Vec2::from_angle(delta_radians * (i + 1) as f32);
+            self.line(center + from * radius, center + to * radius, color);
+        }
+    }
+
+    /// A line from `from` to `to` with a head a quarter of its length.
+    pub fn arrow(&mut self, from: Vec2, to: Vec2, color: Vec4) {
+        self.line(from, to, color);
+        let back = (from - to) * 0.25;
+        for angle in [ARROW_HEAD_ANGLE, -ARROW_HEAD_ANGLE] {
+            self.line(to, to + Vec2::from_angle(angle).rotate(back), color);
+        }
+    }
+}
+
+/// Separate line strips for one frame, joined into a single indexed draw by
+/// primitive restart.
+#[derive(Default)]
+pub struct LineStrips {
+    vertices: Vec<GpuLineVertex>,
+    indices: Vec<u32>,
+}
+
+impl LineStrips {
+    /// Ends a strip in the index buffer.
+    pub const RESTART: u32 = u32::MAX;
+
+    pub fn vertices(&self) -> &[GpuLineVertex] {
+        &self.vertices
+    }
+
+    pub fn indices(&self) -> &[u32] {
+        &self.indices
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    pub fn strip(&mut self, points: impl IntoIterator<Item = (Vec2, Vec4)>) {
+        let start = self.vertices.len();
+        self.vertices
+            .extend(points.into_iter().map(|(position, color)| {
+                GpuLineVertex {
+                    position: position.into(),
+                    color: color.into(),
+                }
+            }));
+        // A single point draws nothing
+        if self.vertices.len() - start < 2 {
+            self.vertices.truncate(start);
+            return;
+        }
+        self.indices
+            .extend(start as u32..self.vertices.len() as u32);
+        self.indices.push(Self::RESTART);
+    }
+}
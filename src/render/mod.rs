@@ -1,14 +1,27 @@
+#[cfg(feature = "web")]
 mod frame_descriptor;
+#[cfg(feature = "web")]
 mod gpu_types;
+#[cfg(feature = "web")]
+mod lines;
+#[cfg(feature = "web")]
 mod pipelines;
+#[cfg(feature = "web")]
+mod resources;
 
+#[cfg(feature = "web")]
 mod wgpu_context;
+#[cfg(feature = "web")]
 pub use wgpu_context::WgpuContext;
 
+#[cfg(feature = "web")]
 mod shader;
+#[cfg(feature = "web")]
 pub use shader::Shader;
 
+#[cfg(feature = "web")]
 mod texture;
+#[cfg(feature = "web")]
 pub use texture::Texture;
 
 mod camera;
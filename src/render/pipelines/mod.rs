@@ -1,13 +1,23 @@
 use super::WgpuContext;
 use wgpu::{PipelineLayout, RenderPipeline};
 
+mod arrows;
+mod field;
+mod lines;
 mod solid;
+mod trails;
 mod wireframe;
 mod world;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum Pipeline {
     Wireframe,
     Solid,
     World,
+    Lines,
+    Trails,
+    Arrows,
+    Field,
 }
 
 impl Pipeline {
@@ -20,6 +30,10 @@ impl Pipeline {
             Pipeline::Wireframe => wireframe::get(context, layout),
             Pipeline::Solid => solid::get(context, layout),
             Pipeline::World => world::get(context, layout),
+            Pipeline::Lines => lines::get(context, layout),
+            Pipeline::Trails => trails::get(context, layout),
+            Pipeline::Arrows => arrows::get(context, layout),
+            Pipeline::Field => field::get(context, layout),
         }
     }
 }
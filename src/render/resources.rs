@@ -0,0 +1,348 @@
+use std::mem;
+use std::num::NonZeroU32;
+
+use wgpu::{
+    util::DeviceExt, BindGroup, BindGroupLayout, Buffer, BufferAddress,
+    BufferUsages, Device, Extent3d, Queue, Texture,
+};
+
+use crate::render::gpu_types::{
+    FieldUniform, GpuArrowVertex, GpuPrimitive, GpuQuad, GpuUniform,
+    FIELD_TEXTURE_WIDTH,
+};
+
+// Smallest allocation for a growable buffer, in bytes
+const MIN_BUFFER_SIZE: BufferAddress = 1024;
+
+/// A vertex or index buffer that is rewritten every frame and only
+/// reallocated when the data outgrows it.
+pub struct DynamicBuffer {
+    label: &'static str,
+    usage: BufferUsages,
+    buffer: Buffer,
+    capacity: BufferAddress,
+}
+
+impl DynamicBuffer {
+    pub fn new(
+        device: &Device,
+        label: &'static str,
+        usage: BufferUsages,
+    ) -> Self {
+        let usage = usage | BufferUsages::COPY_DST;
+        Self {
+            label,
+            usage,
+            buffer: Self::allocate(device, label, usage, MIN_BUFFER_SIZE),
+            capacity: MIN_BUFFER_SIZE,
+        }
+    }
+
+    fn allocate(
+        device: &Device,
+        label: &'static str,
+        usage: BufferUsages,
+        size: BufferAddress,
+    ) -> Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(label),
+            size,
+            usage,
+            mapped_at_creation: false,
+        })
+    }
+
+    /// Uploads `data`, doubling the buffer until it fits.
+    pub fn write(&mut self, device: &Device, queue: &Queue, data: &[u8]) {
+        let size = data.len() as BufferAddress;
+        if size > self.capacity {
+            self.capacity = size.next_power_of_two();
+            self.buffer =
+                Self::allocate(device, self.label, self.usage, self.capacity);
+        }
+        if size > 0 {
+            queue.write_buffer(&self.buffer, 0, data);
+        }
+    }
+
+    pub fn buffer(&self) -> &Buffer {
+        &self.buffer
+    }
+}
+
+/// A uniform buffer and its bind group, kept for the life of the context.
+pub struct UniformBinding {
+    pub buffer: Buffer,
+    pub bind_group: BindGroup,
+    pub layout: BindGroupLayout,
+}
+
+impl UniformBinding {
+    pub fn new(device: &Device, uniform: &impl GpuUniform) -> Self {
+        let (buffer, _, bind_group, layout) = uniform.bind(device);
+        Self {
+            buffer,
+            bind_group,
+            layout,
+        }
+    }
+
+    /// Updates the buffer in place.
+    pub fn write(&self, queue: &Queue, uniform: &impl GpuUniform) {
+        queue.write_buffer(&self.buffer, 0, &uniform.contents());
+    }
+}
+
+/// The field uniform, the texture of body positions and `mu` the field
+/// shader sums over, and the indices of the grid it is drawn on. The texture
+/// grows a row at a time as bodies are added.
+pub struct FieldBinding {
+    buffer: Buffer,
+    bodies: Texture,
+    rows: u32,
+    indices: DynamicBuffer,
+    // The grid resolution `indices` was built for
+    resolution: u32,
+    pub bind_group: BindGroup,
+    pub layout: BindGroupLayout,
+}
+
+impl FieldBinding {
+    pub fn new(device: &Device) -> Self {
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Field Buffer"),
+            size: mem::size_of::<FieldUniform>() as BufferAddress,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::VERTEX,
+                        ty: wgpu::BindingType::Texture {
+                            multisampled: false,
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            // Read with textureLoad, never sampled
+                            sample_type: wgpu::TextureSampleType::Float {
+                                filterable: false,
+                            },
+                        },
+                        count: None,
+                    },
+                ],
+                label: Some("Field Bind Group Layout"),
+            });
+        let bodies = Self::allocate(device, 1);
+        let bind_group = Self::bind(device, &layout, &buffer, &bodies);
+        Self {
+            buffer,
+            bodies,
+            rows: 1,
+            indices: DynamicBuffer::new(
+                device,
+                "Field Index Buffer",
+                BufferUsages::INDEX,
+            ),
+            resolution: 0,
+            bind_group,
+            layout,
+        }
+    }
+
+    pub fn indices(&self) -> &Buffer {
+        self.indices.buffer()
+    }
+
+    fn allocate(device: &Device, rows: u32) -> Texture {
+        device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Field Bodies Texture"),
+            size: Extent3d {
+                width: FIELD_TEXTURE_WIDTH,
+                height: rows,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba32Float,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_DST,
+        })
+    }
+
+    fn bind(
+        device: &Device,
+        layout: &BindGroupLayout,
+        buffer: &Buffer,
+        bodies: &Texture,
+    ) -> BindGroup {
+        let view = bodies.create_view(&wgpu::TextureViewDescriptor::default());
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+            ],
+            label: Some("Field Bind Group"),
+        })
+    }
+
+    /// Uploads the uniform and one texel per body, doubling the texture
+    /// until every body fits. The grid indices are only rebuilt when the
+    /// resolution changes.
+    pub fn write(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        uniform: &FieldUniform,
+        bodies: &[[f32; 4]],
+    ) {
+        queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&[*uniform]));
+        if uniform.resolution != self.resolution {
+            self.resolution = uniform.resolution;
+            self.indices.write(
+                device,
+                queue,
+                bytemuck::cast_slice(&uniform.indices()),
+            );
+        }
+
+        let width = FIELD_TEXTURE_WIDTH as usize;
+        let rows = bodies.len().div_ceil(width) as u32;
+        if rows == 0 {
+            return;
+        }
+        if rows > self.rows {
+            self.rows = rows.next_power_of_two();
+            self.bodies = Self::allocate(device, self.rows);
+            self.bind_group =
+                Self::bind(device, &self.layout, &self.buffer, &self.bodies);
+        }
+        // Only whole rows can be written
+        let mut texels = bodies.to_vec();
+        texels.resize(rows as usize * width, [0.0; 4]);
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &self.bodies,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            bytemuck::cast_slice(&texels),
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: NonZeroU32::new(
+                    FIELD_TEXTURE_WIDTH * mem::size_of::<[f32; 4]>() as u32,
+                ),
+                rows_per_image: NonZeroU32::new(rows),
+            },
+            Extent3d {
+                width: FIELD_TEXTURE_WIDTH,
+                height: rows,
+                depth_or_array_layers: 1,
+            },
+        );
+    }
+}
+
+/// Everything drawn each frame that outlives a single frame.
+pub struct FrameResources {
+    pub quad_vertices: Buffer,
+    pub solid_indices: Buffer,
+    pub wireframe_indices: Buffer,
+    pub arrow_vertices: Buffer,
+    pub instances: DynamicBuffer,
+    pub lines: DynamicBuffer,
+    pub trail_vertices: DynamicBuffer,
+    pub trail_indices: DynamicBuffer,
+    pub arrows: DynamicBuffer,
+    pub camera: UniformBinding,
+    pub world: UniformBinding,
+    pub field: FieldBinding,
+}
+
+impl FrameResources {
+    pub fn new(
+        device: &Device,
+        camera: &impl GpuUniform,
+        world: &impl GpuUniform,
+    ) -> Self {
+        let index_buffer = |label, indices: &[u16]| {
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some(label),
+                contents: bytemuck::cast_slice(indices),
+                usage: BufferUsages::INDEX,
+            })
+        };
+        Self {
+            quad_vertices: device.create_buffer_init(
+                &wgpu::util::BufferInitDescriptor {
+                    label: Some("Vertex Buffer"),
+                    contents: &GpuQuad.data(),
+                    usage: BufferUsages::VERTEX,
+                },
+            ),
+            solid_indices: index_buffer(
+                "Solid Index Buffer",
+                &GpuQuad::indices(false),
+            ),
+            wireframe_indices: index_buffer(
+                "Wireframe Index Buffer",
+                &GpuQuad::indices(true),
+            ),
+            arrow_vertices: device.create_buffer_init(
+                &wgpu::util::BufferInitDescriptor {
+                    label: Some("Arrow Vertex Buffer"),
+                    contents: bytemuck::cast_slice(&GpuArrowVertex::MESH),
+                    usage: BufferUsages::VERTEX,
+                },
+            ),
+            instances: DynamicBuffer::new(
+                device,
+                "Instance Buffer",
+                BufferUsages::VERTEX,
+            ),
+            lines: DynamicBuffer::new(
+                device,
+                "Line Buffer",
+                BufferUsages::VERTEX,
+            ),
+            trail_vertices: DynamicBuffer::new(
+                device,
+                "Trail Vertex Buffer",
+                BufferUsages::VERTEX,
+            ),
+            trail_indices: DynamicBuffer::new(
+                device,
+                "Trail Index Buffer",
+                BufferUsages::INDEX,
+            ),
+            arrows: DynamicBuffer::new(
+                device,
+                "Arrow Instance Buffer",
+                BufferUsages::VERTEX,
+            ),
+            camera: UniformBinding::new(device, camera),
+            world: UniformBinding::new(device, world),
+            field: FieldBinding::new(device),
+        }
+    }
+}
@@ -1,11 +1,18 @@
 use std::collections::HashMap;
 
+use glam::Vec2;
 use gloo_console::log;
-use wgpu::{BindGroup, BindGroupLayout, ShaderModule, Texture};
+use wgpu::{BindGroup, BindGroupLayout, RenderPipeline, ShaderModule, Texture};
 use winit::dpi::PhysicalSize;
 
-use crate::render::{frame_descriptor::FrameDescriptor, pipelines::Pipeline};
-use crate::sim::{Simulation, WORLD_EDGE_SEGMENTS};
+use crate::render::gpu_types::{CameraUniform, WorldUniform};
+use crate::render::resources::FrameResources;
+use crate::render::{
+    frame_descriptor::FrameDescriptor, pipelines::Pipeline, Camera,
+};
+use crate::sim::{
+    BoundaryPolicy, Coloring, Simulation, WORLD_EDGE_SEGMENTS, WORLD_RADIUS,
+};
 
 pub struct WgpuContext {
     pub surface: wgpu::Surface,
@@ -15,6 +22,8 @@ pub struct WgpuContext {
     pub size: winit::dpi::PhysicalSize<u32>,
     shaders: HashMap<&'static str, ShaderModule>,
     textures: HashMap<&'static str, (Texture, BindGroup, BindGroupLayout)>,
+    pipelines: HashMap<Pipeline, RenderPipeline>,
+    resources: FrameResources,
 }
 
 impl WgpuContext {
@@ -63,6 +72,25 @@ impl WgpuContext {
         };
         surface.configure(&device, &config);
 
+        // Placeholder uniforms, overwritten before every frame
+        let camera = Camera::new(
+            Vec2::new(width as f32, height as f32),
+            0.0,
+            Vec2::ZERO,
+            1.0,
+        );
+        let resources = FrameResources::new(
+            &device,
+            &CameraUniform::from(&camera),
+            &WorldUniform::new(
+                WORLD_RADIUS,
+                BoundaryPolicy::default(),
+                false,
+                &Coloring::default(),
+                (0.0, 1.0),
+            ),
+        );
+
         Self {
             surface,
             device,
@@ -71,6 +99,8 @@ impl WgpuContext {
             size: PhysicalSize::new(width, height),
             shaders: HashMap::new(),
             textures: HashMap::new(),
+            pipelines: HashMap::new(),
+            resources,
         }
     }
 
@@ -100,67 +130,70 @@ impl WgpuContext {
             },
         );
 
-        // Create all data from the state we need for a frame
+        // Create all data from the state we need for a frame, and upload it
+        // into the buffers we keep between frames
         let frame_desc = FrameDescriptor::build(sim);
-        let vertex_buffer = frame_desc.create_vertex_buffer(&self.device);
-        let index_buffer = frame_desc.create_index_buffer(&self.device);
-        let (
-            camera_buffer,
-            camera_buffer_contents,
-            camera_bind_group,
-            camera_bind_group_layout,
-        ) = frame_desc.create_camera_binding(&self.device);
-
-        // Data for world boundaries
-        let (
-            world_buffer,
-            world_buffer_contents,
-            world_bind_group,
-            world_bind_group_layout,
-        ) = frame_desc.create_world_data_binding(&self.device);
-        let world_pipeline = {
-            let pipeline_layout = self.device.create_pipeline_layout(
-                &wgpu::PipelineLayoutDescriptor {
-                    label: Some("World Pipeline Layout"),
-                    bind_group_layouts: &[
-                        &camera_bind_group_layout,
-                        &world_bind_group_layout,
-                    ],
-                    push_constant_ranges: &[],
-                },
+        let resources = &mut self.resources;
+        resources
+            .camera
+            .write(&self.queue, &frame_desc.camera_uniform());
+        resources
+            .world
+            .write(&self.queue, &frame_desc.world_uniform());
+        resources.instances.write(
+            &self.device,
+            &self.queue,
+            &frame_desc.instance_data(),
+        );
+        resources.lines.write(
+            &self.device,
+            &self.queue,
+            bytemuck::cast_slice(frame_desc.lines().vertices()),
+        );
+        resources.trail_vertices.write(
+            &self.device,
+            &self.queue,
+            bytemuck::cast_slice(frame_desc.trails().vertices()),
+        );
+        resources.arrows.write(
+            &self.device,
+            &self.queue,
+            bytemuck::cast_slice(frame_desc.arrows()),
+        );
+        if let Some(field) = frame_desc.field_uniform() {
+            resources.field.write(
+                &self.device,
+                &self.queue,
+                field,
+                frame_desc.field_bodies(),
             );
-            Pipeline::World.get(self, pipeline_layout)
-        };
+        }
+        resources.trail_indices.write(
+            &self.device,
+            &self.queue,
+            bytemuck::cast_slice(frame_desc.trails().indices()),
+        );
 
-        let (_, tex_bind_group, tex_bind_group_layout) =
-            self.get_texture(&sim.state.texture_key);
-        let instance_buffer = frame_desc.create_instance_buffer(&self.device);
-        // Get rendering pipeline
-        let pipeline = match &sim.state.wireframe {
-            true => {
-                let pipeline_layout = self.device.create_pipeline_layout(
-                    &wgpu::PipelineLayoutDescriptor {
-                        label: Some("Solid Pipeline Layout"),
-                        bind_group_layouts: &[&camera_bind_group_layout],
-                        push_constant_ranges: &[],
-                    },
-                );
-                Pipeline::Wireframe.get(self, pipeline_layout)
-            }
-            false => {
-                let pipeline_layout = self.device.create_pipeline_layout(
-                    &wgpu::PipelineLayoutDescriptor {
-                        label: Some("Wireframe Pipeline Layout"),
-                        bind_group_layouts: &[
-                            &camera_bind_group_layout,
-                            tex_bind_group_layout,
-                            &world_bind_group_layout,
-                        ],
-                        push_constant_ranges: &[],
-                    },
-                );
-                Pipeline::Solid.get(self, pipeline_layout)
-            }
+        // Get rendering pipelines, building them the first time they're used
+        let body_pipeline = match sim.state.wireframe {
+            true => Pipeline::Wireframe,
+            false => Pipeline::Solid,
+        };
+        for pipeline in [
+            body_pipeline,
+            Pipeline::World,
+            Pipeline::Lines,
+            Pipeline::Trails,
+            Pipeline::Arrows,
+            Pipeline::Field,
+        ] {
+            self.prepare_pipeline(pipeline, &sim.state.texture_key);
+        }
+        let (_, tex_bind_group, _) = self.get_texture(&sim.state.texture_key);
+        let resources = &self.resources;
+        let index_buffer = match sim.state.wireframe {
+            true => &resources.wireframe_indices,
+            false => &resources.solid_indices,
         };
 
         // Execute render pass
@@ -184,16 +217,46 @@ impl WgpuContext {
                     depth_stencil_attachment: None,
                 });
 
+            // Draw the gravitational field as the background
+            if let Some(field) = frame_desc.field_uniform() {
+                pass.set_pipeline(&self.pipelines[&Pipeline::Field]);
+                pass.set_bind_group(0, &resources.field.bind_group, &[]);
+                pass.set_index_buffer(
+                    resources.field.indices().slice(..),
+                    wgpu::IndexFormat::Uint32,
+                );
+                pass.draw_indexed(0..field.index_count(), 0, 0..1);
+            }
+
+            // Draw trails underneath the bodies
+            if !frame_desc.trails().is_empty() {
+                pass.set_pipeline(&self.pipelines[&Pipeline::Trails]);
+                pass.set_bind_group(0, &resources.camera.bind_group, &[]);
+                pass.set_vertex_buffer(
+                    0,
+                    resources.trail_vertices.buffer().slice(..),
+                );
+                pass.set_index_buffer(
+                    resources.trail_indices.buffer().slice(..),
+                    wgpu::IndexFormat::Uint32,
+                );
+                pass.draw_indexed(
+                    0..frame_desc.trails().indices().len() as u32,
+                    0,
+                    0..1,
+                );
+            }
+
             // Draw world data
-            pass.set_pipeline(&pipeline);
-            pass.set_bind_group(0, &camera_bind_group, &[]);
+            pass.set_pipeline(&self.pipelines[&body_pipeline]);
+            pass.set_bind_group(0, &resources.camera.bind_group, &[]);
             if !sim.state.wireframe {
                 pass.set_bind_group(1, tex_bind_group, &[]);
             }
-            pass.set_bind_group(2, &world_bind_group, &[]);
+            pass.set_bind_group(2, &resources.world.bind_group, &[]);
 
-            pass.set_vertex_buffer(0, vertex_buffer.slice(..));
-            pass.set_vertex_buffer(1, instance_buffer.slice(..));
+            pass.set_vertex_buffer(0, resources.quad_vertices.slice(..));
+            pass.set_vertex_buffer(1, resources.instances.buffer().slice(..));
             pass.set_index_buffer(
                 index_buffer.slice(..),
                 wgpu::IndexFormat::Uint16,
@@ -205,17 +268,31 @@ impl WgpuContext {
             );
 
             // Draw world boundaries
-            pass.set_pipeline(&world_pipeline);
-            pass.set_bind_group(0, &camera_bind_group, &[]);
-            pass.set_bind_group(1, &world_bind_group, &[]);
+            pass.set_pipeline(&self.pipelines[&Pipeline::World]);
+            pass.set_bind_group(0, &resources.camera.bind_group, &[]);
+            pass.set_bind_group(1, &resources.world.bind_group, &[]);
             pass.draw(0..(WORLD_EDGE_SEGMENTS + 1), 0..1);
-        }
 
-        // Write buffers
-        self.queue
-            .write_buffer(&camera_buffer, 0, &camera_buffer_contents);
-        self.queue
-            .write_buffer(&world_buffer, 0, &world_buffer_contents);
+            // Draw velocity and acceleration arrows
+            if !frame_desc.arrows().is_empty() {
+                pass.set_pipeline(&self.pipelines[&Pipeline::Arrows]);
+                pass.set_bind_group(0, &resources.camera.bind_group, &[]);
+                pass.set_vertex_buffer(0, resources.arrow_vertices.slice(..));
+                pass.set_vertex_buffer(1, resources.arrows.buffer().slice(..));
+                pass.draw(
+                    0..frame_desc.arrow_mesh_len(),
+                    0..frame_desc.arrows().len() as u32,
+                );
+            }
+
+            // Draw overlay lines, only when there are any
+            if !frame_desc.lines().is_empty() {
+                pass.set_pipeline(&self.pipelines[&Pipeline::Lines]);
+                pass.set_bind_group(0, &resources.camera.bind_group, &[]);
+                pass.set_vertex_buffer(0, resources.lines.buffer().slice(..));
+                pass.draw(0..frame_desc.lines().vertices().len() as u32, 0..1);
+            }
+        }
 
         // Submit queue
         self.queue.submit(std::iter::once(encoder.finish()));
@@ -223,6 +300,52 @@ impl WgpuContext {
         Ok(())
     }
 
+    // Builds a pipeline and its layout once, the first time it's needed
+    fn prepare_pipeline(&mut self, pipeline: Pipeline, texture_key: &str) {
+        if self.pipelines.contains_key(&pipeline) {
+            return;
+        }
+        let resources = &self.resources;
+        let (label, bind_group_layouts) = match pipeline {
+            Pipeline::Solid => (
+                "Solid Pipeline Layout",
+                vec![
+                    &resources.camera.layout,
+                    &self.get_texture(texture_key).2,
+                    &resources.world.layout,
+                ],
+            ),
+            Pipeline::Wireframe => {
+                ("Wireframe Pipeline Layout", vec![&resources.camera.layout])
+            }
+            Pipeline::World => (
+                "World Pipeline Layout",
+                vec![&resources.camera.layout, &resources.world.layout],
+            ),
+            Pipeline::Lines => {
+                ("Lines Pipeline Layout", vec![&resources.camera.layout])
+            }
+            Pipeline::Trails => {
+                ("Trails Pipeline Layout", vec![&resources.camera.layout])
+            }
+            Pipeline::Arrows => {
+                ("Arrows Pipeline Layout", vec![&resources.camera.layout])
+            }
+            Pipeline::Field => {
+                ("Field Pipeline Layout", vec![&resources.field.layout])
+            }
+        };
+        let layout = self.device.create_pipeline_layout(
+            &wgpu::PipelineLayoutDescriptor {
+                label: Some(label),
+                bind_group_layouts: &bind_group_layouts,
+                push_constant_ranges: &[],
+            },
+        );
+        let render_pipeline = pipeline.get(self, layout);
+        self.pipelines.insert(pipeline, render_pipeline);
+    }
+
     pub fn add_shader(&mut self, name: &'static str, source: &'static str) {
         if self.shaders.contains_key(name) {
             panic!("Shader with name '{}' already exists", name);
@@ -1,12 +1,20 @@
+use std::fmt;
+
 use glam::Vec2;
 use winit::event::Event;
 use winit::event::WindowEvent;
 use winit::event_loop::{ControlFlow, EventLoopWindowTarget};
 use winit::window::Window;
 
-use crate::dom::Dom;
+use crate::dom::{self, Dom};
 use crate::render::WgpuContext;
-use crate::sim::{Simulation, WORLD_RADIUS};
+use crate::sim::{
+    Action, ActionMap, ColorRange, Inspection, Simulation, SimulationConfig,
+    Snapshot, VelocityInit,
+};
+
+const SNAPSHOT_STORAGE_KEY: &str = "snapshot";
+const BINDINGS_STORAGE_KEY: &str = "bindings";
 
 pub struct Runtime {
     context: WgpuContext,
@@ -17,16 +25,79 @@ pub struct Runtime {
 
 impl Runtime {
     pub fn new(context: WgpuContext, window: Window, dom: Dom) -> Self {
-        let mut sim = Simulation::default();
+        // Scenes are reproducible from the URL, e.g. `?seed=42&bodies=1000`
+        let mut config = SimulationConfig {
+            seed: js_sys::Date::now() as u64,
+            ..Default::default()
+        };
+        if let Some(seed) = query_param(&dom, "seed", str::parse) {
+            config.seed = seed;
+        }
+        if let Some(bodies) = query_param(&dom, "bodies", str::parse) {
+            config.num_bodies = bodies;
+        }
+        let velocity = query_param(&dom, "velocities", |value| match value {
+            "orbital" => Ok(SimulationConfig::default().velocity),
+            "random" => Ok(VelocityInit::Random),
+            _ => Err("expected orbital or random"),
+        });
+        if let Some(velocity) = velocity {
+            config.velocity = velocity;
+        }
+        if let Some(preset) = query_param(&dom, "preset", str::parse) {
+            config.preset = Some(preset);
+        }
+        dom.log_list.log_message(&format!(
+            "Seed: {}, Bodies: {}",
+            config.seed, config.num_bodies
+        ));
+        if let Some(preset) = config.preset {
+            dom.log_list.log_message(&format!("Preset: {}", preset));
+        }
+
+        let mut sim = Simulation::new(config);
+        if let Some(length) = query_param(&dom, "trails", str::parse) {
+            sim.trails.set_length(length);
+        }
+        // Body colors, e.g. `?color=speed&colormap=magma&range=0,10`
+        let coloring = &mut sim.state.coloring;
+        if let Some(attribute) = query_param(&dom, "color", str::parse) {
+            coloring.attribute = attribute;
+            coloring.range = ColorRange::Auto;
+        }
+        if let Some(colormap) = query_param(&dom, "colormap", str::parse) {
+            coloring.colormap = colormap;
+        }
+        if let Some(range) = query_param(&dom, "range", str::parse) {
+            coloring.range = range;
+        }
+        // Field overlay, sampled on a grid of this many cells a side
+        if let Some(resolution) = query_param(&dom, "field", str::parse) {
+            sim.state.field.enabled = true;
+            sim.state.field.set_resolution(resolution);
+        }
+        // Rebound controls are kept in local storage under `bindings`
+        if let Some(source) = dom::load_from_storage(BINDINGS_STORAGE_KEY) {
+            match ActionMap::from_ron(&source) {
+                Ok(actions) => {
+                    sim.state.input_controller.actions = actions;
+                    dom.log_list.log_message("Loaded key bindings");
+                }
+                Err(e) => dom
+                    .log_list
+                    .log_message(&format!("Bad key bindings: {}", e)),
+            }
+        }
         // Zoom into sim
         let view_size = Vec2::new(
             window.inner_size().width as f32,
             window.inner_size().height as f32,
         );
+        let world_radius = sim.physics_context.world_radius;
         sim.state.zoom = if view_size.y < view_size.x {
-            view_size.y / (WORLD_RADIUS * 2.0)
+            view_size.y / (world_radius * 2.0)
         } else {
-            view_size.x / (WORLD_RADIUS * 2.0)
+            view_size.x / (world_radius * 2.0)
         };
 
         Self {
@@ -46,9 +117,6 @@ impl Runtime {
         // Log every event
         self.dom.log_list.log_event(&event);
 
-        // Update world
-        self.sim.update();
-
         // Handle events
         match event {
             Event::WindowEvent {
@@ -69,6 +137,11 @@ impl Runtime {
                 }
             }
             Event::MainEventsCleared => {
+                self.handle_snapshots();
+
+                // Update world once per frame
+                self.sim.update();
+
                 // RedrawRequested will only trigger once, unless we manually
                 // request it.
                 self.window.request_redraw();
@@ -80,6 +153,18 @@ impl Runtime {
                     Ok(_) => {
                         // Update frame count
                         self.dom.fps_counter.update();
+                        self.dom
+                            .diagnostics_panel
+                            .update(&self.sim.diagnostics);
+                        let inspection =
+                            self.sim.state.selected.and_then(|id| {
+                                Inspection::measure(
+                                    &self.sim.physics_context,
+                                    id,
+                                )
+                            });
+                        self.dom.inspector_panel.update(inspection.as_ref());
+                        self.dom.clock.update(&self.sim);
                     }
                     // Reconfigure the surface if lost
                     Err(wgpu::SurfaceError::Lost) => {
@@ -99,4 +184,50 @@ impl Runtime {
             _ => (),
         }
     }
+
+    // Save and restore the simulation through local storage
+    fn handle_snapshots(&mut self) {
+        let input = &self.sim.state.input_controller;
+        if input.is_action_pressed(Action::SaveSnapshot) {
+            let snapshot = self.sim.snapshot().to_ron();
+            match dom::save_to_storage(SNAPSHOT_STORAGE_KEY, &snapshot) {
+                true => self.dom.log_list.log_message("Saved snapshot"),
+                false => {
+                    self.dom.log_list.log_message("Could not save snapshot")
+                }
+            }
+        } else if input.is_action_pressed(Action::LoadSnapshot) {
+            match dom::load_from_storage(SNAPSHOT_STORAGE_KEY).map(|source| {
+                Snapshot::from_ron(&source)
+                    .and_then(|snapshot| self.sim.restore(&snapshot))
+            }) {
+                Some(Ok(())) => {
+                    self.dom.log_list.log_message("Restored snapshot");
+                }
+                Some(Err(e)) => self
+                    .dom
+                    .log_list
+                    .log_message(&format!("Bad snapshot: {}", e)),
+                None => self.dom.log_list.log_message("No saved snapshot"),
+            }
+        }
+    }
+}
+
+// Parses the URL parameter `name`, if given. A bad value is logged and
+// ignored, leaving the default in place.
+fn query_param<T, E: fmt::Display>(
+    dom: &Dom,
+    name: &str,
+    parse: impl FnOnce(&str) -> Result<T, E>,
+) -> Option<T> {
+    let value = dom::get_query_param(name)?;
+    match parse(&value) {
+        Ok(parsed) => Some(parsed),
+        Err(e) => {
+            dom.log_list
+                .log_message(&format!("Ignoring {}={}: {}", name, value, e));
+            None
+        }
+    }
 }
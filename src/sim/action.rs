@@ -0,0 +1,262 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use winit::event::{MouseButton, VirtualKeyCode};
+
+use crate::sim::scenario::ron_options;
+
+/// Something the user can do, independent of which key does it.
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize,
+)]
+pub enum Action {
+    TogglePause,
+    /// Advance exactly one physics step while paused
+    SingleStep,
+    SpeedUp,
+    SlowDown,
+    ToggleReverse,
+    ToggleWireframe,
+    CycleTexture,
+    ToggleTrails,
+    ToggleVectors,
+    CycleColorAttribute,
+    CycleColormap,
+    /// Show the potential, then the field strength, then neither
+    CycleField,
+    ToggleContours,
+    ToggleBarnesHut,
+    ToggleMerging,
+    CycleIntegrator,
+    CycleBoundary,
+    SaveSnapshot,
+    LoadSnapshot,
+    /// Select a body, or drag to launch a new one
+    Launch,
+    Deselect,
+    GrowBody,
+    ShrinkBody,
+    /// Held while launching for a denser body
+    HeavyBody,
+    PanUp,
+    PanLeft,
+    PanDown,
+    PanRight,
+    /// Held while moving the mouse to drag the view
+    DragPan,
+    ZoomIn,
+    ZoomOut,
+    RotateLeft,
+    RotateRight,
+    CycleCameraTarget,
+}
+
+/// A key or mouse button.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Trigger {
+    Key(VirtualKeyCode),
+    Mouse(MouseButton),
+}
+
+/// A trigger and the modifiers that must be held with it. Ctrl and Alt must
+/// match exactly, so Ctrl+S doesn't also fire S; Shift may be held anyway,
+/// as it changes what some actions do, e.g. launching heavy bodies.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Binding {
+    pub trigger: Trigger,
+    #[serde(default)]
+    pub shift: bool,
+    #[serde(default)]
+    pub ctrl: bool,
+    #[serde(default)]
+    pub alt: bool,
+}
+
+impl Binding {
+    pub fn key(key: VirtualKeyCode) -> Self {
+        Self::new(Trigger::Key(key))
+    }
+
+    pub fn mouse(button: MouseButton) -> Self {
+        Self::new(Trigger::Mouse(button))
+    }
+
+    fn new(trigger: Trigger) -> Self {
+        Self {
+            trigger,
+            shift: false,
+            ctrl: false,
+            alt: false,
+        }
+    }
+}
+
+/// Which bindings perform each action.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActionMap {
+    pub bindings: BTreeMap<Action, Vec<Binding>>,
+}
+
+impl Default for ActionMap {
+    fn default() -> Self {
+        use Action::*;
+        use VirtualKeyCode as Key;
+
+        let keys = [
+            (TogglePause, Key::Space),
+            (SingleStep, Key::Period),
+            (SpeedUp, Key::Equals),
+            (SpeedUp, Key::NumpadAdd),
+            (SlowDown, Key::Minus),
+            (SlowDown, Key::NumpadSubtract),
+            (ToggleReverse, Key::R),
+            (ToggleWireframe, Key::Q),
+            (CycleTexture, Key::E),
+            (ToggleTrails, Key::T),
+            (ToggleVectors, Key::V),
+            (CycleColorAttribute, Key::C),
+            (CycleColormap, Key::X),
+            (CycleField, Key::P),
+            (ToggleContours, Key::O),
+            (ToggleBarnesHut, Key::G),
+            (ToggleMerging, Key::M),
+            (CycleIntegrator, Key::I),
+            (CycleBoundary, Key::B),
+            (SaveSnapshot, Key::K),
+            (LoadSnapshot, Key::L),
+            (Deselect, Key::Escape),
+            (GrowBody, Key::RBracket),
+            (ShrinkBody, Key::LBracket),
+            (HeavyBody, Key::LShift),
+            (HeavyBody, Key::RShift),
+            (PanUp, Key::W),
+            (PanLeft, Key::A),
+            (PanDown, Key::S),
+            (PanRight, Key::D),
+            (ZoomIn, Key::Up),
+            (ZoomOut, Key::Down),
+            (RotateLeft, Key::Left),
+            (RotateRight, Key::Right),
+            (CycleCameraTarget, Key::F),
+        ]
+        .map(|(action, key)| (action, Binding::key(key)));
+        let buttons = [
+            (Launch, MouseButton::Left),
+            (DragPan, MouseButton::Middle),
+            (DragPan, MouseButton::Right),
+        ]
+        .map(|(action, button)| (action, Binding::mouse(button)));
+
+        let mut bindings = BTreeMap::<Action, Vec<Binding>>::new();
+        for (action, binding) in keys.into_iter().chain(buttons) {
+            bindings.entry(action).or_default().push(binding);
+        }
+        Self { bindings }
+    }
+}
+
+impl ActionMap {
+    /// Reads a map of actions to bindings, e.g.
+    /// `{ PanUp: [(trigger: Key(Z))], SaveSnapshot: [(trigger: Key(S), ctrl:
+    /// true)] }`. Actions that aren't listed keep their default bindings.
+    pub fn from_ron(source: &str) -> Result<Self, String> {
+        let overrides: BTreeMap<Action, Vec<Binding>> =
+            ron_options().from_str(source).map_err(|e| e.to_string())?;
+        let mut map = Self::default();
+        map.bindings.extend(overrides);
+        Ok(map)
+    }
+
+    pub fn to_ron(&self) -> String {
+        ron_options()
+            .to_string_pretty(&self.bindings, ron::ser::PrettyConfig::default())
+            .expect("Action map is always serializable")
+    }
+
+    pub fn bindings(&self, action: Action) -> &[Binding] {
+        self.bindings.get(&action).map_or(&[], Vec::as_slice)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use winit::event::{MouseButton, VirtualKeyCode};
+
+    use super::{Action, ActionMap, Binding, Trigger};
+    use crate::sim::input::InputController;
+
+    #[test]
+    fn default_map_round_trips() {
+        let map = ActionMap::default();
+        assert_eq!(ActionMap::from_ron(&map.to_ron()), Ok(map));
+    }
+
+    #[test]
+    fn overrides_replace_only_listed_actions() {
+        let map = ActionMap::from_ron(
+            "{ PanUp: [(trigger: Key(Z))], \
+             SaveSnapshot: [(trigger: Key(S), ctrl: true)] }",
+        )
+        .unwrap();
+        assert_eq!(
+            map.bindings(Action::PanUp),
+            [Binding::key(VirtualKeyCode::Z)]
+        );
+        assert_eq!(
+            map.bindings(Action::SaveSnapshot),
+            [Binding {
+                ctrl: true,
+                ..Binding::key(VirtualKeyCode::S)
+            }]
+        );
+        assert_eq!(
+            map.bindings(Action::Launch),
+            [Binding::mouse(MouseButton::Left)]
+        );
+        assert!(ActionMap::from_ron("{ Jump: [] }").is_err());
+    }
+
+    #[test]
+    fn actions_need_their_modifiers() {
+        let mut input = InputController::default();
+        input.actions.bindings.insert(
+            Action::SaveSnapshot,
+            vec![Binding {
+                ctrl: true,
+                ..Binding::key(VirtualKeyCode::S)
+            }],
+        );
+
+        input.press(VirtualKeyCode::S);
+        assert!(input.is_action_pressed(Action::PanDown));
+        assert!(!input.is_action_pressed(Action::SaveSnapshot));
+
+        input.update();
+        input.release(VirtualKeyCode::S);
+        input.press(VirtualKeyCode::RControl);
+        input.update();
+        input.press(VirtualKeyCode::S);
+        assert!(input.is_action_pressed(Action::SaveSnapshot));
+        assert!(!input.is_action_active(Action::PanDown));
+
+        input.update();
+        assert!(!input.is_action_pressed(Action::SaveSnapshot));
+        input.release(VirtualKeyCode::S);
+        assert!(input.is_action_released(Action::SaveSnapshot));
+
+        // Ctrl held as a trigger of its own still counts
+        input.actions.bindings.insert(
+            Action::Deselect,
+            vec![Binding::key(VirtualKeyCode::RControl)],
+        );
+        assert!(input.is_action_active(Action::Deselect));
+        input.release(VirtualKeyCode::RControl);
+
+        input.press_button(MouseButton::Right);
+        assert!(input.is_action_active(Action::DragPan));
+        assert_eq!(
+            input.actions.bindings(Action::DragPan)[0].trigger,
+            Trigger::Mouse(MouseButton::Middle)
+        );
+    }
+}
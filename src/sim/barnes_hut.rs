@@ -0,0 +1,343 @@
+use glam::Vec2;
+
+use crate::sim::Softening;
+
+// Quadrants deeper than this are treated as a single point, which stops
+// coincident bodies from splitting forever.
+const MAX_DEPTH: usize = 32;
+
+// Child offsets, indexed by `Node::quadrant`
+const QUADRANTS: [Vec2; 4] = [
+    Vec2::new(-1.0, -1.0),
+    Vec2::new(1.0, -1.0),
+    Vec2::new(-1.0, 1.0),
+    Vec2::new(1.0, 1.0),
+];
+
+/// A Barnes-Hut quadtree over point masses `(position, mu)`.
+///
+/// Far away groups of bodies are approximated by their center of mass, which
+/// brings the cost of a full acceleration pass down to O(n log n).
+pub struct QuadTree {
+    nodes: Vec<Node>,
+}
+
+struct Node {
+    center: Vec2,
+    half_size: f32,
+    mu: f32,
+    center_of_mass: Vec2,
+    // Index of the first of four consecutive children, if subdivided
+    children: Option<usize>,
+    // The single point held by a leaf
+    point: Option<(Vec2, f32)>,
+}
+
+impl Node {
+    fn new(center: Vec2, half_size: f32) -> Self {
+        Self {
+            center,
+            half_size,
+            mu: 0.0,
+            center_of_mass: Vec2::ZERO,
+            children: None,
+            point: None,
+        }
+    }
+
+    fn quadrant(&self, position: Vec2) -> usize {
+        let right = (position.x >= self.center.x) as usize;
+        let top = (position.y >= self.center.y) as usize;
+        right | (top << 1)
+    }
+}
+
+impl QuadTree {
+    pub fn new(points: impl IntoIterator<Item = (Vec2, f32)>) -> Self {
+        let points = points.into_iter().collect::<Vec<_>>();
+
+        // Fit a square root node around every point
+        let (min, max) = points.iter().fold(
+            (Vec2::splat(f32::MAX), Vec2::splat(f32::MIN)),
+            |(min, max), (position, _)| {
+                (min.min(*position), max.max(*position))
+            },
+        );
+        let (center, half_size) = if points.is_empty() {
+            (Vec2::ZERO, 1.0)
+        } else {
+            (
+                (min + max) / 2.0,
+                ((max - min).max_element() / 2.0).max(1.0),
+            )
+        };
+
+        let mut tree = Self {
+            nodes: vec![Node::new(center, half_size)],
+        };
+        for (position, mu) in points {
+            tree.insert(position, mu);
+        }
+        tree.summarize(0);
+        tree
+    }
+
+    fn insert(&mut self, position: Vec2, mu: f32) {
+        let mut index = 0;
+        let mut depth = 0;
+        loop {
+            let node = &mut self.nodes[index];
+            match (node.children, node.point) {
+                // Internal node, descend into the matching quadrant
+                (Some(first_child), _) => {
+                    index = first_child + node.quadrant(position);
+                }
+                // Empty leaf, claim it
+                (None, None) => {
+                    node.point = Some((position, mu));
+                    return;
+                }
+                // Occupied leaf at the depth limit, merge the points
+                (None, Some((other, other_mu))) if depth >= MAX_DEPTH => {
+                    let total = other_mu + mu;
+                    let merged = if total != 0.0 {
+                        (other * other_mu + position * mu) / total
+                    } else {
+                        other
+                    };
+                    node.point = Some((merged, total));
+                    return;
+                }
+                // Occupied leaf, split it and push the old point down
+                (None, Some((other, other_mu))) => {
+                    let first_child = self.subdivide(index);
+                    let node = &self.nodes[index];
+                    let child = first_child + node.quadrant(other);
+                    index = first_child + node.quadrant(position);
+                    self.nodes[child].point = Some((other, other_mu));
+                }
+            }
+            depth += 1;
+        }
+    }
+
+    fn subdivide(&mut self, index: usize) -> usize {
+        let first_child = self.nodes.len();
+        let (center, half_size) = {
+            let node = &mut self.nodes[index];
+            node.point = None;
+            node.children = Some(first_child);
+            (node.center, node.half_size / 2.0)
+        };
+        for offset in QUADRANTS {
+            self.nodes
+                .push(Node::new(center + offset * half_size, half_size));
+        }
+        first_child
+    }
+
+    // Accumulate mass and center of mass bottom-up
+    fn summarize(&mut self, index: usize) -> (f32, Vec2) {
+        let node = &self.nodes[index];
+        let (mu, weighted) = match (node.children, node.point) {
+            (Some(first_child), _) => (first_child..first_child + 4).fold(
+                (0.0, Vec2::ZERO),
+                |(mu, weighted), child| {
+                    let (child_mu, child_com) = self.summarize(child);
+                    (mu + child_mu, weighted + child_com * child_mu)
+                },
+            ),
+            (None, Some((position, mu))) => (mu, position * mu),
+            (None, None) => (0.0, Vec2::ZERO),
+        };
+
+        let node = &mut self.nodes[index];
+        node.mu = mu;
+        node.center_of_mass = match (node.point, mu != 0.0) {
+            (Some((position, _)), _) => position,
+            (None, true) => weighted / mu,
+            (None, false) => node.center,
+        };
+        (node.mu, node.center_of_mass)
+    }
+
+    /// Gravitational acceleration at `position`.
+    ///
+    /// A node is opened when its width over its distance to `position` is at
+    /// least `theta`. A `theta` of zero always opens and yields the exact sum.
+    pub fn acceleration(
+        &self,
+        position: Vec2,
+        theta: f32,
+        softening: Softening,
+    ) -> Vec2 {
+        let mut acceleration = Vec2::ZERO;
+        let mut stack = vec![0];
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if node.mu == 0.0 {
+                continue;
+            }
+
+            let dir = node.center_of_mass - position;
+            let mag_2 = dir.length_squared();
+            let width = 2.0 * node.half_size;
+            match node.children {
+                Some(first_child) if width * width >= theta * theta * mag_2 => {
+                    stack.extend(first_child..first_child + 4);
+                }
+                // Bodies exert no force on themselves
+                _ if mag_2 == 0.0 => (),
+                _ => acceleration += softening.acceleration(dir, node.mu),
+            }
+        }
+        acceleration
+    }
+
+    /// Gravitational potential per unit mass at `position`, opening nodes
+    /// the same way as [`QuadTree::acceleration`].
+    pub fn potential(
+        &self,
+        position: Vec2,
+        theta: f32,
+        softening: Softening,
+    ) -> f32 {
+        let mut potential = 0.0;
+        let mut stack = vec![0];
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if node.mu == 0.0 {
+                continue;
+            }
+
+            let mag_2 = node.center_of_mass.distance_squared(position);
+            let width = 2.0 * node.half_size;
+            match node.children {
+                Some(first_child) if width * width >= theta * theta * mag_2 => {
+                    stack.extend(first_child..first_child + 4);
+                }
+                // Bodies have no potential energy with themselves
+                _ if mag_2 == 0.0 => (),
+                _ => potential += softening.potential(mag_2.sqrt(), node.mu),
+            }
+        }
+        potential
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec2;
+    use particular::prelude::Particle;
+    use particular::ParticleSet;
+
+    use super::QuadTree;
+    use crate::sim::Softening;
+
+    struct PointMass {
+        position: Vec2,
+        mu: f32,
+    }
+
+    impl Particle for PointMass {
+        type Vector = Vec2;
+
+        fn position(&self) -> Vec2 {
+            self.position
+        }
+
+        fn mu(&self) -> f32 {
+            self.mu
+        }
+    }
+
+    // Small deterministic generator so the tests don't need a RNG crate
+    fn random_points(n: usize) -> Vec<(Vec2, f32)> {
+        let mut seed = 0x2545_f491_u32;
+        let mut next = move || {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            seed as f32 / u32::MAX as f32
+        };
+        (0..n)
+            .map(|_| {
+                let position = Vec2::new(next(), next()) * 150.0 - 75.0;
+                let mu = 0.1 + next();
+                (position, mu)
+            })
+            .collect()
+    }
+
+    fn exact_accelerations(points: &[(Vec2, f32)]) -> Vec<(Vec2, Vec2)> {
+        let mut set = ParticleSet::new();
+        for &(position, mu) in points {
+            set.add(PointMass { position, mu });
+        }
+        set.result()
+            .map(|(particle, acceleration)| (particle.position, acceleration))
+            .collect()
+    }
+
+    // Errors are relative to the mean exact magnitude, since bodies near
+    // the center feel an almost cancelled net force
+    fn relative_errors(points: &[(Vec2, f32)], theta: f32) -> Vec<f32> {
+        let tree = QuadTree::new(points.iter().copied());
+        let exact = exact_accelerations(points);
+        let scale = exact.iter().map(|(_, a)| a.length()).sum::<f32>()
+            / exact.len() as f32;
+        exact
+            .into_iter()
+            .map(|(position, exact)| {
+                let approx =
+                    tree.acceleration(position, theta, Softening::None);
+                (approx - exact).length() / scale
+            })
+            .collect()
+    }
+
+    #[test]
+    fn zero_theta_matches_exact() {
+        let points = random_points(200);
+        for error in relative_errors(&points, 0.0) {
+            assert!(error < 1e-4, "relative error {}", error);
+        }
+    }
+
+    #[test]
+    fn approximates_exact_within_tolerance() {
+        let points = random_points(1000);
+        let errors = relative_errors(&points, 0.5);
+        let mean = errors.iter().sum::<f32>() / errors.len() as f32;
+        let max = errors.iter().cloned().fold(0.0, f32::max);
+        assert!(mean < 0.01, "mean relative error {}", mean);
+        assert!(max < 0.2, "max relative error {}", max);
+    }
+
+    #[test]
+    fn potential_approximates_exact_sum() {
+        let points = random_points(500);
+        let tree = QuadTree::new(points.iter().copied());
+        for &(position, _) in points.iter().step_by(25) {
+            let exact = points
+                .iter()
+                .filter(|(other, _)| *other != position)
+                .map(|(other, mu)| -mu / other.distance(position))
+                .sum::<f32>();
+            let potential =
+                |theta| tree.potential(position, theta, Softening::None);
+            assert!((potential(0.0) - exact).abs() < 1e-4 * exact.abs());
+            assert!((potential(0.5) - exact).abs() < 0.01 * exact.abs());
+        }
+    }
+
+    #[test]
+    fn coincident_points_are_finite() {
+        let points = [(Vec2::ONE, 1.0); 8];
+        let tree = QuadTree::new(points.iter().copied());
+        let acceleration =
+            |position| tree.acceleration(position, 0.5, Softening::None);
+        assert_eq!(acceleration(Vec2::ONE), Vec2::ZERO);
+        assert!(acceleration(Vec2::ZERO).is_finite());
+    }
+}
@@ -1,15 +1,49 @@
+use std::f32::consts::PI;
+use std::fmt;
+
 use glam::Vec2;
 use particular::prelude::Particle;
 use rapier2d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Identifies a body for as long as it exists. Ids are never reused by the
+/// same [`PhysicsContext`].
+///
+/// [`PhysicsContext`]: crate::sim::physics::PhysicsContext
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    Serialize,
+    Deserialize,
+)]
+#[serde(transparent)]
+pub struct BodyId(pub(crate) u64);
 
-use crate::sim::{GRAVITY_AMPLIFIER, UNIVERSAL_GRAVITY};
+impl fmt::Display for BodyId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
 
-#[derive(Default)]
+#[derive(Clone, Default)]
 pub struct Body {
+    id: BodyId,
     position: Vec2,
     rotation: f32,
+    previous_position: Vec2,
+    previous_rotation: f32,
     radius: f32,
     mass: f32,
+    acceleration: Vec2,
+    collisions: u32,
+    gravitational_constant: f32,
     pub rigidbody_handle: RigidBodyHandle,
     pub collider_handle: ColliderHandle,
 }
@@ -22,24 +56,32 @@ impl Particle for Body {
     }
 
     fn mu(&self) -> f32 {
-        self.mass * GRAVITY_AMPLIFIER * UNIVERSAL_GRAVITY
+        self.mass * self.gravitational_constant
     }
 }
 
 impl Body {
     pub fn new(
+        id: BodyId,
         rigidbody_handle: RigidBodyHandle,
         collider_handle: ColliderHandle,
+        gravitational_constant: f32,
     ) -> Self {
         Self {
+            id,
             rigidbody_handle,
             collider_handle,
+            gravitational_constant,
             ..Default::default()
         }
     }
 }
 
 impl Body {
+    pub fn id(&self) -> BodyId {
+        self.id
+    }
+
     pub fn position(&self) -> Vec2 {
         self.position
     }
@@ -56,6 +98,37 @@ impl Body {
         self.mass
     }
 
+    /// The gravitational acceleration applied in the last step.
+    pub fn acceleration(&self) -> Vec2 {
+        self.acceleration
+    }
+
+    /// How many times the body has started touching another.
+    pub fn collisions(&self) -> u32 {
+        self.collisions
+    }
+
+    pub fn add_collisions(&mut self, count: u32) {
+        self.collisions += count;
+    }
+
+    pub fn interpolated_position(&self, alpha: f32) -> Vec2 {
+        self.previous_position.lerp(self.position, alpha)
+    }
+
+    pub fn interpolated_rotation(&self, alpha: f32) -> f32 {
+        // Take the short way around
+        let delta = (self.rotation() - self.previous_rotation + PI)
+            .rem_euclid(2.0 * PI)
+            - PI;
+        self.previous_rotation + delta * alpha
+    }
+
+    pub fn save_previous(&mut self) {
+        self.previous_position = self.position;
+        self.previous_rotation = self.rotation;
+    }
+
     pub fn sync_to_rigidbody(
         &mut self,
         bodies: &RigidBodySet,
@@ -70,11 +143,16 @@ impl Body {
         self.mass = rb.mass();
     }
 
+    pub fn set_acceleration(&mut self, acceleration: Vec2) {
+        self.acceleration = acceleration;
+    }
+
     pub fn apply_acceleration_to_rigidbody(
-        &self,
+        &mut self,
         bodies: &mut RigidBodySet,
         acceleration: <Self as Particle>::Vector,
     ) {
+        self.set_acceleration(acceleration);
         let rb = bodies.get_mut(self.rigidbody_handle).unwrap();
         let force = acceleration * self.mass();
 
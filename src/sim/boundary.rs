@@ -0,0 +1,131 @@
+use std::f32::consts::PI;
+
+use glam::Vec2;
+use rapier2d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::sim::{WORLD_EDGE_SEGMENTS, WORLD_RESTITUTION};
+
+/// What happens to bodies reaching the edge of the world.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum BoundaryPolicy {
+    /// Bodies bounce off a static circular wall
+    Wall { restitution: f32 },
+    /// Bodies leaving the world come back in on the opposite side
+    Wrap,
+    /// Bodies leaving the world are removed
+    Despawn,
+    /// Bodies may leave the world
+    Open,
+}
+
+impl Default for BoundaryPolicy {
+    fn default() -> Self {
+        BoundaryPolicy::Wall {
+            restitution: WORLD_RESTITUTION,
+        }
+    }
+}
+
+impl BoundaryPolicy {
+    /// The next policy, for cycling through them.
+    pub fn next(self) -> Self {
+        match self {
+            BoundaryPolicy::Wall { .. } => BoundaryPolicy::Wrap,
+            BoundaryPolicy::Wrap => BoundaryPolicy::Despawn,
+            BoundaryPolicy::Despawn => BoundaryPolicy::Open,
+            BoundaryPolicy::Open => BoundaryPolicy::default(),
+        }
+    }
+
+    /// Identifies the policy to the world shaders.
+    pub fn shader_mode(&self) -> u32 {
+        match self {
+            BoundaryPolicy::Wall { .. } => 0,
+            BoundaryPolicy::Wrap => 1,
+            BoundaryPolicy::Despawn => 2,
+            BoundaryPolicy::Open => 3,
+        }
+    }
+}
+
+// A closed polyline along the world edge, matching the drawn ring
+pub(crate) fn wall_collider(world_radius: f32, restitution: f32) -> Collider {
+    let delta_radians = 2.0 * PI / WORLD_EDGE_SEGMENTS as f32;
+    let vertices = (0..WORLD_EDGE_SEGMENTS)
+        .map(|i| {
+            let (y, x) = (delta_radians * i as f32).sin_cos();
+            point![x * world_radius, y * world_radius]
+        })
+        .collect();
+    let indices = (0..WORLD_EDGE_SEGMENTS)
+        .map(|i| [i, (i + 1) % WORLD_EDGE_SEGMENTS])
+        .collect();
+    ColliderBuilder::polyline(vertices, Some(indices))
+        .restitution(restitution)
+        .friction(0.0)
+        .build()
+}
+
+// Mirrors a position outside the world through the center, so it comes back
+// in just inside the opposite edge
+pub(crate) fn wrap_position(position: Vec2, world_radius: f32) -> Vec2 {
+    let distance = position.length();
+    let inside = (2.0 * world_radius - distance).max(0.0);
+    -position / distance * inside
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec2;
+
+    use crate::sim::{BodyDescriptor, BoundaryPolicy, Globals, Scenario};
+
+    // A lone body heading straight out of the world
+    fn escape(boundary: BoundaryPolicy) -> Vec<Vec2> {
+        let globals = Globals {
+            world_radius: 10.0,
+            boundary,
+            ..Default::default()
+        };
+        let body = BodyDescriptor {
+            velocity: Vec2::new(20.0, 0.0),
+            ..BodyDescriptor::new(Vec2::new(8.0, 0.0), 0.5)
+        };
+        let mut physics_context =
+            Scenario::new(globals, vec![body]).build().unwrap();
+        for _ in 0..12 {
+            physics_context.step();
+        }
+        physics_context
+            .bodies()
+            .map(|body| body.position())
+            .collect()
+    }
+
+    #[test]
+    fn wall_keeps_bodies_inside() {
+        let positions = escape(BoundaryPolicy::default());
+        assert_eq!(positions.len(), 1);
+        assert!(positions[0].length() < 10.0, "{}", positions[0]);
+    }
+
+    #[test]
+    fn wrap_brings_bodies_back_on_the_other_side() {
+        let positions = escape(BoundaryPolicy::Wrap);
+        assert_eq!(positions.len(), 1);
+        assert!(positions[0].length() < 10.0, "{}", positions[0]);
+        assert!(positions[0].x < 0.0, "{}", positions[0]);
+    }
+
+    #[test]
+    fn despawn_removes_bodies() {
+        assert!(escape(BoundaryPolicy::Despawn).is_empty());
+    }
+
+    #[test]
+    fn open_lets_bodies_leave() {
+        let positions = escape(BoundaryPolicy::Open);
+        assert!(positions[0].length() > 10.0, "{}", positions[0]);
+    }
+}
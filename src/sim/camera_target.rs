@@ -0,0 +1,187 @@
+use std::collections::HashMap;
+
+use glam::{IVec2, Vec2};
+
+use crate::sim::physics::PhysicsContext;
+use crate::sim::{BodyId, CAM_CLUSTER_CELL};
+
+/// What the camera keeps centered.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CameraTarget {
+    /// Panned by hand
+    #[default]
+    Free,
+    Body(BodyId),
+    CenterOfMass,
+    /// Where the most mass is packed together
+    DensestCluster,
+}
+
+impl CameraTarget {
+    /// The world position to center on, if any, between the last two steps
+    /// like the bodies are drawn.
+    pub fn position(
+        &self,
+        physics_context: &PhysicsContext,
+        alpha: f32,
+    ) -> Option<Vec2> {
+        let points = || {
+            physics_context.bodies().map(move |body| {
+                (body.interpolated_position(alpha), body.mass())
+            })
+        };
+        match self {
+            CameraTarget::Free => None,
+            CameraTarget::Body(id) => physics_context
+                .body(*id)
+                .map(|body| body.interpolated_position(alpha)),
+            CameraTarget::CenterOfMass => center_of_mass(points()),
+            CameraTarget::DensestCluster => {
+                densest_cluster(&points().collect::<Vec<_>>(), CAM_CLUSTER_CELL)
+            }
+        }
+    }
+
+    /// The next mode to try, following `selected` if there is one.
+    pub fn next(&self, selected: Option<BodyId>) -> Self {
+        match (self, selected) {
+            (CameraTarget::Free, Some(id)) => CameraTarget::Body(id),
+            (CameraTarget::Free, None) | (CameraTarget::Body(_), _) => {
+                CameraTarget::CenterOfMass
+            }
+            (CameraTarget::CenterOfMass, _) => CameraTarget::DensestCluster,
+            (CameraTarget::DensestCluster, _) => CameraTarget::Free,
+        }
+    }
+}
+
+fn center_of_mass(points: impl Iterator<Item = (Vec2, f32)>) -> Option<Vec2> {
+    let (weighted, total) = points.fold(
+        (Vec2::ZERO, 0.0),
+        |(weighted, total), (position, mass)| {
+            (weighted + position * mass, total + mass)
+        },
+    );
+    (total > 0.0).then(|| weighted / total)
+}
+
+// Bins bodies into a grid of `cell` sized squares, finds the 3x3 block of
+// cells holding the most mass and returns its center of mass
+fn densest_cluster(points: &[(Vec2, f32)], cell: f32) -> Option<Vec2> {
+    let cell_of = |position: Vec2| (position / cell).floor().as_ivec2();
+    let mut grid = HashMap::<IVec2, f32>::new();
+    for (position, mass) in points {
+        *grid.entry(cell_of(*position)).or_default() += mass;
+    }
+
+    let neighbors = |center: IVec2| {
+        (-1..=1)
+            .flat_map(move |x| (-1..=1).map(move |y| center + IVec2::new(x, y)))
+    };
+    let densest = grid
+        .keys()
+        .map(|key| {
+            let mass = neighbors(*key)
+                .filter_map(|key| grid.get(&key))
+                .sum::<f32>();
+            (*key, mass)
+        })
+        // Break ties by cell so the pick doesn't depend on hash order
+        .max_by(|(a_key, a), (b_key, b)| {
+            a.total_cmp(b)
+                .then_with(|| (b_key.x, b_key.y).cmp(&(a_key.x, a_key.y)))
+        })?
+        .0;
+
+    center_of_mass(points.iter().copied().filter(|(position, _)| {
+        (cell_of(*position) - densest).abs().max_element() <= 1
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec2;
+
+    use super::{densest_cluster, CameraTarget};
+    use crate::sim::{BodyDescriptor, Globals, Scenario};
+
+    #[test]
+    fn finds_the_heaviest_clump() {
+        let mut points = vec![];
+        // A light, wide spread and a tight, heavy clump
+        for i in 0..20 {
+            let angle = i as f32 * 0.3;
+            points.push((Vec2::from_angle(angle) * 50.0, 1.0));
+        }
+        for i in 0..5 {
+            points.push((Vec2::new(30.0 + i as f32 * 0.5, -20.0), 4.0));
+        }
+        let center = densest_cluster(&points, 5.0).unwrap();
+        assert!(center.distance(Vec2::new(31.0, -20.0)) < 1e-4, "{}", center);
+        assert!(densest_cluster(&[], 5.0).is_none());
+    }
+
+    #[test]
+    fn follows_targets() {
+        let bodies = vec![
+            BodyDescriptor {
+                mass: Some(3.0),
+                ..BodyDescriptor::new(Vec2::new(-1.0, 0.0), 0.5)
+            },
+            BodyDescriptor {
+                mass: Some(1.0),
+                ..BodyDescriptor::new(Vec2::new(3.0, 2.0), 0.5)
+            },
+        ];
+        let mut physics_context =
+            Scenario::new(Globals::default(), bodies).build().unwrap();
+        let ids = physics_context
+            .bodies()
+            .map(|body| body.id())
+            .collect::<Vec<_>>();
+
+        let center = CameraTarget::CenterOfMass
+            .position(&physics_context, 1.0)
+            .unwrap();
+        assert!(center.distance(Vec2::new(0.0, 0.5)) < 1e-4, "{}", center);
+        assert_eq!(
+            CameraTarget::Body(ids[1]).position(&physics_context, 1.0),
+            Some(Vec2::new(3.0, 2.0))
+        );
+        assert_eq!(CameraTarget::Free.position(&physics_context, 1.0), None);
+
+        // Halfway through the next step
+        physics_context.step();
+        let moved = physics_context.body(ids[0]).unwrap();
+        assert_ne!(moved.position(), Vec2::new(-1.0, 0.0));
+        let halfway = (Vec2::new(-1.0, 0.0) + moved.position()) / 2.0;
+        let target = CameraTarget::Body(ids[0]).position(&physics_context, 0.5);
+        assert!(target.unwrap().distance(halfway) < 1e-6);
+
+        // A lost body has nowhere to follow
+        physics_context.despawn(ids[1]);
+        assert_eq!(
+            CameraTarget::Body(ids[1]).position(&physics_context, 1.0),
+            None
+        );
+    }
+
+    #[test]
+    fn cycles_through_modes() {
+        let mut target = CameraTarget::Free;
+        let mut seen = vec![];
+        for _ in 0..4 {
+            target = target.next(None);
+            seen.push(target);
+        }
+        assert_eq!(
+            seen,
+            [
+                CameraTarget::CenterOfMass,
+                CameraTarget::DensestCluster,
+                CameraTarget::Free,
+                CameraTarget::CenterOfMass,
+            ]
+        );
+    }
+}
@@ -0,0 +1,305 @@
+use std::collections::HashMap;
+use std::f32::consts::PI;
+use std::hash::Hash;
+
+use glam::Vec2;
+use serde::{Deserialize, Serialize};
+
+use crate::sim::BodyDescriptor;
+
+/// How touching bodies interact.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum CollisionMode {
+    /// Bodies bounce off each other as rigid contacts
+    #[default]
+    Bounce,
+    /// Touching bodies combine into one, sized by `density`
+    Merge { density: DensityModel },
+}
+
+/// Decides the radius of a merged body.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum DensityModel {
+    /// The merged body covers the combined area of its parts
+    #[default]
+    ConserveArea,
+    /// Every merged body has the same surface density
+    Constant { density: f32 },
+}
+
+impl DensityModel {
+    fn radius(&self, mass: f32, parts: &[BodyDescriptor]) -> f32 {
+        match self {
+            DensityModel::ConserveArea => parts
+                .iter()
+                .map(|part| part.radius.powi(2))
+                .sum::<f32>()
+                .sqrt(),
+            DensityModel::Constant { density } => {
+                (mass / (PI * density)).sqrt()
+            }
+        }
+    }
+}
+
+// Moment of inertia of a uniform disc, as rapier computes it for balls
+fn inertia(mass: f32, radius: f32) -> f32 {
+    0.5 * mass * radius * radius
+}
+
+/// Combines bodies into one, conserving mass, linear momentum and angular
+/// momentum. Every part must have an exact `mass`.
+pub fn merge(
+    parts: &[BodyDescriptor],
+    density: DensityModel,
+) -> BodyDescriptor {
+    let mass_of = |part: &BodyDescriptor| {
+        part.mass.expect("merged bodies need an exact mass")
+    };
+    let mass = parts.iter().map(mass_of).sum::<f32>();
+    let position = parts
+        .iter()
+        .map(|part| part.position * mass_of(part))
+        .sum::<Vec2>()
+        / mass;
+    let velocity = parts
+        .iter()
+        .map(|part| part.velocity * mass_of(part))
+        .sum::<Vec2>()
+        / mass;
+
+    // Spin plus orbital angular momentum about the new center of mass
+    let angular_momentum = parts
+        .iter()
+        .map(|part| {
+            let part_mass = mass_of(part);
+            inertia(part_mass, part.radius) * part.angular_velocity
+                + part_mass
+                    * (part.position - position)
+                        .perp_dot(part.velocity - velocity)
+        })
+        .sum::<f32>();
+
+    let radius = density.radius(mass, parts);
+    let heaviest = parts
+        .iter()
+        .max_by(|a, b| mass_of(a).total_cmp(&mass_of(b)))
+        .expect("merging no bodies");
+
+    BodyDescriptor {
+        id: None,
+        position,
+        velocity,
+        rotation: heaviest.rotation,
+        angular_velocity: angular_momentum / inertia(mass, radius),
+        radius,
+        density: None,
+        mass: Some(mass),
+        restitution: heaviest.restitution,
+        friction: heaviest.friction,
+    }
+}
+
+/// Groups everything connected by a chain of contacts, so that a body
+/// touching two others in the same step merges with both.
+pub(crate) fn merge_groups<T: Copy + Eq + Hash>(
+    contacts: impl IntoIterator<Item = (T, T)>,
+) -> Vec<Vec<T>> {
+    // Union-find over the bodies in contact
+    let mut parents = HashMap::new();
+    fn root<T: Copy + Eq + Hash>(parents: &mut HashMap<T, T>, item: T) -> T {
+        let parent = *parents.entry(item).or_insert(item);
+        if parent == item {
+            return item;
+        }
+        let root = root(parents, parent);
+        parents.insert(item, root);
+        root
+    }
+    for (a, b) in contacts {
+        let (root_a, root_b) = (root(&mut parents, a), root(&mut parents, b));
+        if root_a != root_b {
+            parents.insert(root_a, root_b);
+        }
+    }
+
+    let mut groups = HashMap::<T, Vec<T>>::new();
+    let items = parents.keys().copied().collect::<Vec<_>>();
+    for item in items {
+        let root = root(&mut parents, item);
+        groups.entry(root).or_default().push(item);
+    }
+    groups.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec2;
+
+    use super::{merge, merge_groups, CollisionMode, DensityModel};
+    use crate::sim::physics::PhysicsContext;
+    use crate::sim::{BodyDescriptor, Globals, Scenario};
+
+    fn part(
+        position: Vec2,
+        velocity: Vec2,
+        angular_velocity: f32,
+        mass: f32,
+        radius: f32,
+    ) -> BodyDescriptor {
+        BodyDescriptor {
+            velocity,
+            angular_velocity,
+            mass: Some(mass),
+            ..BodyDescriptor::new(position, radius)
+        }
+    }
+
+    // Mass, linear momentum and angular momentum about the origin
+    fn totals(parts: &[BodyDescriptor]) -> (f32, Vec2, f32) {
+        parts
+            .iter()
+            .fold((0.0, Vec2::ZERO, 0.0), |(m, p, l), part| {
+                let mass = part.mass.unwrap();
+                (
+                    m + mass,
+                    p + part.velocity * mass,
+                    l + 0.5
+                        * mass
+                        * part.radius.powi(2)
+                        * part.angular_velocity
+                        + mass * part.position.perp_dot(part.velocity),
+                )
+            })
+    }
+
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() <= 1e-3 * a.abs().max(1.0), "{} != {}", a, b);
+    }
+
+    #[test]
+    fn merge_conserves_mass_and_momentum() {
+        let parts = [
+            part(Vec2::new(1.0, 2.0), Vec2::new(0.5, -1.0), 0.3, 2.0, 0.5),
+            part(Vec2::new(1.8, 2.4), Vec2::new(-2.0, 0.0), -1.0, 5.0, 0.7),
+            part(Vec2::new(0.4, 2.9), Vec2::new(0.0, 3.0), 2.0, 1.0, 0.3),
+        ];
+        let merged = merge(&parts, DensityModel::ConserveArea);
+
+        let (mass, momentum, angular_momentum) = totals(&parts);
+        let (merged_mass, merged_momentum, merged_angular_momentum) =
+            totals(std::slice::from_ref(&merged));
+        assert_close(merged_mass, mass);
+        assert_close(merged_momentum.x, momentum.x);
+        assert_close(merged_momentum.y, momentum.y);
+        assert_close(merged_angular_momentum, angular_momentum);
+        assert_close(
+            merged.radius.powi(2),
+            0.5f32.powi(2) + 0.7f32.powi(2) + 0.3f32.powi(2),
+        );
+    }
+
+    #[test]
+    fn constant_density_sets_radius() {
+        let parts = [
+            part(Vec2::ZERO, Vec2::ZERO, 0.0, 3.0, 1.0),
+            part(Vec2::X, Vec2::ZERO, 0.0, 1.0, 1.0),
+        ];
+        let density = DensityModel::Constant { density: 2.0 };
+        let merged = merge(&parts, density);
+        assert_close(
+            merged.radius,
+            (4.0 / (std::f32::consts::PI * 2.0)).sqrt(),
+        );
+    }
+
+    #[test]
+    fn chained_contacts_form_one_group() {
+        let mut groups = merge_groups([(1, 2), (3, 2), (4, 5)]);
+        for group in &mut groups {
+            group.sort();
+        }
+        groups.sort();
+        assert_eq!(groups, vec![vec![1, 2, 3], vec![4, 5]]);
+    }
+
+    fn context_totals(physics_context: &PhysicsContext) -> (f32, Vec2, f32) {
+        let parts = physics_context
+            .bodies()
+            .map(|body| {
+                BodyDescriptor::from_rigid_body(
+                    &physics_context.rigid_body_set[body.rigidbody_handle],
+                    &physics_context.collider_set[body.collider_handle],
+                )
+            })
+            .collect::<Vec<_>>();
+        totals(&parts)
+    }
+
+    #[test]
+    fn colliding_bodies_merge() {
+        // An off-center hit, so there is angular momentum to keep
+        let bodies = vec![
+            part(Vec2::new(-3.0, 0.2), Vec2::new(4.0, 0.0), 0.5, 2.0, 0.5),
+            part(Vec2::new(3.0, -0.2), Vec2::new(-3.0, 0.5), 0.0, 3.0, 0.8),
+            part(Vec2::new(0.0, 30.0), Vec2::ZERO, 0.0, 1.0, 0.5),
+        ];
+        let mut physics_context =
+            Scenario::new(Globals::default(), bodies).build().unwrap();
+        physics_context.collision_mode = CollisionMode::Merge {
+            density: DensityModel::ConserveArea,
+        };
+
+        let (mass, momentum, angular_momentum) =
+            context_totals(&physics_context);
+        for _ in 0..120 {
+            physics_context.step();
+        }
+        let (merged_mass, merged_momentum, merged_angular_momentum) =
+            context_totals(&physics_context);
+
+        assert_eq!(physics_context.bodies().count(), 2);
+        assert_eq!(
+            physics_context.rigid_body_set.len(),
+            2,
+            "merged bodies are removed from rapier too"
+        );
+        assert_close(merged_mass, mass);
+        assert_close(merged_momentum.x, momentum.x);
+        assert_close(merged_momentum.y, momentum.y);
+        assert_close(merged_angular_momentum, angular_momentum);
+
+        let merged = physics_context
+            .bodies()
+            .find(|body| body.mass() > 4.0)
+            .unwrap();
+        assert_close(merged.radius(), (0.5f32.powi(2) + 0.8f32.powi(2)).sqrt());
+    }
+
+    #[test]
+    fn bodies_already_touching_merge() {
+        let bodies = vec![
+            part(Vec2::new(-0.5, 0.0), Vec2::ZERO, 0.0, 2.0, 0.5),
+            part(Vec2::new(0.5, 0.0), Vec2::ZERO, 0.0, 2.0, 0.5),
+        ];
+        let mut physics_context =
+            Scenario::new(Globals::default(), bodies).build().unwrap();
+        for _ in 0..10 {
+            physics_context.step();
+        }
+        assert_eq!(physics_context.bodies().count(), 2);
+        // A single touch, however long it lasts
+        let collisions = physics_context
+            .bodies()
+            .map(|body| body.collisions())
+            .collect::<Vec<_>>();
+        assert_eq!(collisions, [1, 1]);
+
+        // Merging only just turned on, long after they first touched
+        physics_context.collision_mode = CollisionMode::Merge {
+            density: DensityModel::ConserveArea,
+        };
+        physics_context.step();
+        assert_eq!(physics_context.bodies().count(), 1);
+    }
+}
@@ -0,0 +1,459 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+
+use glam::{IVec2, Vec2};
+use serde::{Deserialize, Serialize};
+
+use crate::sim::collision::merge_groups;
+use crate::sim::physics::PhysicsContext;
+use crate::sim::{Body, BodyId, BODY_MAX_RADIUS, COLOR_CLUSTER_CELL};
+
+const GOLDEN_RATIO_CONJUGATE: f64 = 0.618_033_988_749_895;
+
+/// Which property of a body sets its color.
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+pub enum ColorAttribute {
+    #[default]
+    Radius,
+    Speed,
+    /// Translational kinetic energy
+    KineticEnergy,
+    /// Magnitude of the gravitational acceleration
+    Acceleration,
+    Mass,
+    /// A distinct color per body, so merged bodies stand out from the rest
+    Id,
+    /// A distinct color per group of bodies close together
+    Cluster,
+    /// How many times the body has hit another
+    Collisions,
+}
+
+impl ColorAttribute {
+    pub const ALL: [ColorAttribute; 8] = [
+        ColorAttribute::Radius,
+        ColorAttribute::Speed,
+        ColorAttribute::KineticEnergy,
+        ColorAttribute::Acceleration,
+        ColorAttribute::Mass,
+        ColorAttribute::Id,
+        ColorAttribute::Cluster,
+        ColorAttribute::Collisions,
+    ];
+
+    /// The name used to pick the attribute, e.g. `?color=speed`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ColorAttribute::Radius => "radius",
+            ColorAttribute::Speed => "speed",
+            ColorAttribute::KineticEnergy => "kinetic-energy",
+            ColorAttribute::Acceleration => "acceleration",
+            ColorAttribute::Mass => "mass",
+            ColorAttribute::Id => "id",
+            ColorAttribute::Cluster => "cluster",
+            ColorAttribute::Collisions => "collisions",
+        }
+    }
+
+    /// The next attribute, for cycling through them.
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|a| *a == self).unwrap();
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    /// The attribute of a single body. Seen on its own, a body is a cluster
+    /// of one.
+    pub fn value(&self, physics_context: &PhysicsContext, body: &Body) -> f32 {
+        let velocity = || {
+            let rb = &physics_context.rigid_body_set[body.rigidbody_handle];
+            Vec2::from(*rb.linvel())
+        };
+        match self {
+            ColorAttribute::Radius => body.radius(),
+            ColorAttribute::Speed => velocity().length(),
+            ColorAttribute::KineticEnergy => {
+                0.5 * body.mass() * velocity().length_squared()
+            }
+            ColorAttribute::Acceleration => body.acceleration().length(),
+            ColorAttribute::Mass => body.mass(),
+            ColorAttribute::Id | ColorAttribute::Cluster => spread(body.id()),
+            ColorAttribute::Collisions => body.collisions() as f32,
+        }
+    }
+}
+
+/// Turns a value between 0 and 1 into a color, in the body shader.
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+pub enum Colormap {
+    /// White through blue, yellow and orange to red, like stars
+    #[default]
+    Stellar,
+    Viridis,
+    Magma,
+    Cividis,
+}
+
+impl Colormap {
+    pub const ALL: [Colormap; 4] = [
+        Colormap::Stellar,
+        Colormap::Viridis,
+        Colormap::Magma,
+        Colormap::Cividis,
+    ];
+
+    /// The name used to pick the colormap, e.g. `?colormap=magma`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Colormap::Stellar => "stellar",
+            Colormap::Viridis => "viridis",
+            Colormap::Magma => "magma",
+            Colormap::Cividis => "cividis",
+        }
+    }
+
+    /// The next colormap, for cycling through them.
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|c| *c == self).unwrap();
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    /// Identifies the colormap to the body shader.
+    pub fn shader_mode(&self) -> u32 {
+        match self {
+            Colormap::Stellar => 0,
+            Colormap::Viridis => 1,
+            Colormap::Magma => 2,
+            Colormap::Cividis => 3,
+        }
+    }
+}
+
+impl fmt::Display for ColorAttribute {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for ColorAttribute {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        ColorAttribute::ALL
+            .into_iter()
+            .find(|attribute| attribute.name() == name)
+            .ok_or_else(|| {
+                let names =
+                    ColorAttribute::ALL.map(|attribute| attribute.name());
+                format!(
+                    "unknown color attribute {} (expected one of {})",
+                    name,
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+impl fmt::Display for Colormap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Colormap {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Colormap::ALL
+            .into_iter()
+            .find(|colormap| colormap.name() == name)
+            .ok_or_else(|| {
+                let names = Colormap::ALL.map(|colormap| colormap.name());
+                format!(
+                    "unknown colormap {} (expected one of {})",
+                    name,
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+/// The values mapped to either end of the colormap.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ColorRange {
+    /// Stretched over every body, on screen or not, every frame
+    Auto,
+    Manual {
+        min: f32,
+        max: f32,
+    },
+}
+
+// A manual range, written `min,max`
+impl FromStr for ColorRange {
+    type Err = String;
+
+    fn from_str(range: &str) -> Result<Self, Self::Err> {
+        range
+            .split_once(',')
+            .and_then(|(min, max)| {
+                Some(ColorRange::Manual {
+                    min: min.trim().parse().ok()?,
+                    max: max.trim().parse().ok()?,
+                })
+            })
+            .ok_or_else(|| "expected two numbers, e.g. 0,10".to_owned())
+    }
+}
+
+/// How bodies are colored.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Coloring {
+    pub attribute: ColorAttribute,
+    pub colormap: Colormap,
+    pub range: ColorRange,
+}
+
+impl Default for Coloring {
+    fn default() -> Self {
+        Self {
+            attribute: ColorAttribute::Radius,
+            colormap: Colormap::Stellar,
+            range: ColorRange::Manual {
+                min: 0.0,
+                max: BODY_MAX_RADIUS,
+            },
+        }
+    }
+}
+
+impl Coloring {
+    /// Colors by the next attribute, fitting the range to it.
+    pub fn cycle_attribute(&mut self) {
+        self.attribute = self.attribute.next();
+        self.range = ColorRange::Auto;
+    }
+
+    /// The attribute of every body, in the order of `bodies()`.
+    pub fn values(&self, physics_context: &PhysicsContext) -> Vec<f32> {
+        match self.attribute {
+            ColorAttribute::Cluster => clusters(physics_context),
+            attribute => physics_context
+                .bodies()
+                .map(|body| attribute.value(physics_context, body))
+                .collect(),
+        }
+    }
+
+    /// The values at either end of the colormap, given every body's value.
+    pub fn bounds(&self, values: &[f32]) -> (f32, f32) {
+        match (self.range, self.attribute) {
+            (ColorRange::Manual { min, max }, _) => (min, max),
+            // Ids are already spread over the whole map
+            (
+                ColorRange::Auto,
+                ColorAttribute::Id | ColorAttribute::Cluster,
+            ) => (0.0, 1.0),
+            (ColorRange::Auto, _) => values
+                .iter()
+                .filter(|value| value.is_finite())
+                .fold(None, |bounds, &value| match bounds {
+                    None => Some((value, value)),
+                    Some((min, max)) => {
+                        Some((f32::min(min, value), f32::max(max, value)))
+                    }
+                })
+                .unwrap_or((0.0, 1.0)),
+        }
+    }
+}
+
+// Spreads consecutive ids evenly around the colormap
+fn spread(id: BodyId) -> f32 {
+    (id.0 as f64 * GOLDEN_RATIO_CONJUGATE).fract() as f32
+}
+
+// Links bodies in neighboring cells of a grid into clusters, each colored
+// like the smallest id in it so it keeps its color as bodies come and go
+fn clusters(physics_context: &PhysicsContext) -> Vec<f32> {
+    let cells = physics_context
+        .bodies()
+        .map(|body| (body.position() / COLOR_CLUSTER_CELL).floor().as_ivec2())
+        .collect::<Vec<_>>();
+    let occupied = cells.iter().copied().collect::<HashSet<_>>();
+    // Half the neighbors are enough as links go both ways, and linking a
+    // cell to itself keeps lone cells
+    let links = occupied.iter().flat_map(|&cell| {
+        [
+            IVec2::ZERO,
+            IVec2::X,
+            IVec2::ONE,
+            IVec2::Y,
+            IVec2::new(-1, 1),
+        ]
+        .map(|offset| (cell, cell + offset))
+        .into_iter()
+        .filter(|(_, neighbor)| occupied.contains(neighbor))
+    });
+
+    let mut cluster_of = HashMap::new();
+    for (cluster, group) in merge_groups(links).into_iter().enumerate() {
+        for cell in group {
+            cluster_of.insert(cell, cluster);
+        }
+    }
+    let mut smallest = HashMap::<usize, BodyId>::new();
+    for (body, cell) in physics_context.bodies().zip(&cells) {
+        let id = smallest.entry(cluster_of[cell]).or_insert(body.id());
+        *id = (*id).min(body.id());
+    }
+    cells
+        .iter()
+        .map(|cell| spread(smallest[&cluster_of[cell]]))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec2;
+
+    use super::{ColorAttribute, ColorRange, Coloring, Colormap};
+    use crate::sim::physics::PhysicsContext;
+    use crate::sim::{BodyDescriptor, Globals, Scenario};
+
+    fn scenario() -> PhysicsContext {
+        let bodies = vec![
+            BodyDescriptor {
+                velocity: Vec2::new(3.0, 4.0),
+                mass: Some(2.0),
+                ..BodyDescriptor::new(Vec2::new(-10.0, 0.0), 0.5)
+            },
+            BodyDescriptor {
+                mass: Some(1.0),
+                ..BodyDescriptor::new(Vec2::new(10.0, 0.0), 1.0)
+            },
+        ];
+        Scenario::new(Globals::default(), bodies).build().unwrap()
+    }
+
+    #[test]
+    fn measures_attributes() {
+        let physics_context = scenario();
+        let values = |attribute| {
+            Coloring {
+                attribute,
+                ..Default::default()
+            }
+            .values(&physics_context)
+        };
+
+        assert_eq!(values(ColorAttribute::Radius), [0.5, 1.0]);
+        assert_eq!(values(ColorAttribute::Speed), [5.0, 0.0]);
+        let energy = values(ColorAttribute::KineticEnergy);
+        assert!((energy[0] - 25.0).abs() < 1e-4, "{:?}", energy);
+        assert_eq!(energy[1], 0.0);
+        assert_eq!(values(ColorAttribute::Collisions), [0.0, 0.0]);
+        let ids = values(ColorAttribute::Id);
+        assert!(ids.iter().all(|id| (0.0..1.0).contains(id)));
+        assert_ne!(ids[0], ids[1]);
+    }
+
+    #[test]
+    fn fits_the_range() {
+        let mut coloring = Coloring::default();
+        assert_eq!(coloring.bounds(&[0.2, 3.0]), (0.0, 1.0));
+
+        coloring.cycle_attribute();
+        assert_eq!(coloring.attribute, ColorAttribute::Speed);
+        assert_eq!(coloring.range, ColorRange::Auto);
+        assert_eq!(coloring.bounds(&[2.0, f32::NAN, -1.0, 4.0]), (-1.0, 4.0));
+        assert_eq!(coloring.bounds(&[]), (0.0, 1.0));
+
+        coloring.attribute = ColorAttribute::Id;
+        assert_eq!(coloring.bounds(&[0.3]), (0.0, 1.0));
+    }
+
+    #[test]
+    fn groups_clusters() {
+        let positions = [
+            Vec2::new(-20.0, 0.0),
+            Vec2::new(20.0, 0.0),
+            Vec2::new(-18.0, 1.0),
+            Vec2::new(0.0, 20.0),
+            Vec2::new(-15.0, 4.5),
+            Vec2::new(21.0, -1.0),
+        ];
+        let bodies = positions
+            .into_iter()
+            .map(|position| BodyDescriptor {
+                mass: Some(1.0),
+                ..BodyDescriptor::new(position, 0.5)
+            })
+            .collect();
+        let physics_context =
+            Scenario::new(Globals::default(), bodies).build().unwrap();
+        let values = Coloring {
+            attribute: ColorAttribute::Cluster,
+            ..Default::default()
+        }
+        .values(&physics_context);
+
+        // Colored like the first body of each cluster
+        assert_eq!(values[2], values[0]);
+        assert_eq!(values[4], values[0]);
+        assert_eq!(values[5], values[1]);
+        let ids = Coloring {
+            attribute: ColorAttribute::Id,
+            ..Default::default()
+        }
+        .values(&physics_context);
+        assert_eq!([values[0], values[1], values[3]], [ids[0], ids[1], ids[3]]);
+    }
+
+    #[test]
+    fn counts_collisions() {
+        let bodies = vec![
+            BodyDescriptor {
+                velocity: Vec2::new(5.0, 0.0),
+                ..BodyDescriptor::new(Vec2::new(-2.0, 0.0), 0.5)
+            },
+            BodyDescriptor {
+                velocity: Vec2::new(-5.0, 0.0),
+                ..BodyDescriptor::new(Vec2::new(2.0, 0.0), 0.5)
+            },
+            BodyDescriptor::new(Vec2::new(0.0, 20.0), 0.5),
+        ];
+        let mut physics_context =
+            Scenario::new(Globals::default(), bodies).build().unwrap();
+        for _ in 0..60 {
+            physics_context.step();
+        }
+        let counts = Coloring {
+            attribute: ColorAttribute::Collisions,
+            ..Default::default()
+        }
+        .values(&physics_context);
+        assert!(counts[0] >= 1.0 && counts[0] == counts[1], "{:?}", counts);
+        assert_eq!(counts[2], 0.0);
+    }
+
+    #[test]
+    fn names_round_trip() {
+        for attribute in ColorAttribute::ALL {
+            assert_eq!(attribute.name().parse(), Ok(attribute));
+        }
+        for colormap in Colormap::ALL {
+            assert_eq!(colormap.to_string().parse(), Ok(colormap));
+        }
+        assert!("rainbow".parse::<Colormap>().is_err());
+        assert_eq!(
+            "0, 2.5".parse(),
+            Ok(ColorRange::Manual { min: 0.0, max: 2.5 })
+        );
+        assert!("10".parse::<ColorRange>().is_err());
+    }
+}
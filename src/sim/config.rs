@@ -0,0 +1,187 @@
+use std::f32::consts::PI;
+use std::ops::Mul;
+
+use glam::{Mat3, Vec2, Vec3, Vec3Swizzles};
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg32;
+
+use crate::sim::{
+    BodyDescriptor, Globals, Preset, Scenario, Softening, BODY_MAX_ANG_VEL,
+    BODY_MAX_LIN_VEL, BODY_MAX_RADIUS, BODY_SOFTENING_LENGTH,
+    BODY_VELOCITY_DISPERSION, CENTRAL_BODY_MASS, CENTRAL_BODY_RADIUS,
+    DEFAULT_NUM_BODIES, DEFAULT_SEED,
+};
+
+/// How generated bodies start moving.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum VelocityInit {
+    /// A random direction, at up to `max_lin_vel`
+    Random,
+    /// Near circular orbits around the center of mass, so the disc rotates
+    /// instead of collapsing. `dispersion` scales a random velocity added
+    /// on top, relative to the orbital speed.
+    Orbital { dispersion: f32 },
+}
+
+/// Parameters for generating the initial bodies of a [`Simulation`].
+///
+/// The same config always generates the same bodies.
+///
+/// [`Simulation`]: crate::sim::Simulation
+#[derive(Clone, Debug, PartialEq)]
+pub struct SimulationConfig {
+    pub seed: u64,
+    pub num_bodies: usize,
+    pub max_radius: f32,
+    pub max_lin_vel: f32,
+    pub max_ang_vel: f32,
+    pub softening: Softening,
+    pub velocity: VelocityInit,
+    /// Mass of a body fixed at the center, if any
+    pub central_mass: Option<f32>,
+    /// Generates this preset instead of a random disc
+    pub preset: Option<Preset>,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        Self {
+            seed: DEFAULT_SEED,
+            num_bodies: DEFAULT_NUM_BODIES,
+            max_radius: BODY_MAX_RADIUS,
+            max_lin_vel: BODY_MAX_LIN_VEL,
+            max_ang_vel: BODY_MAX_ANG_VEL,
+            softening: Softening::Plummer {
+                length: BODY_SOFTENING_LENGTH,
+            },
+            velocity: VelocityInit::Orbital {
+                dispersion: BODY_VELOCITY_DISPERSION,
+            },
+            central_mass: Some(CENTRAL_BODY_MASS),
+            preset: None,
+        }
+    }
+}
+
+impl SimulationConfig {
+    /// Scatters bodies over a disc filling the world, unless a preset is
+    /// chosen.
+    pub fn generate(&self) -> Scenario {
+        if let Some(preset) = self.preset {
+            return preset.scenario(self.num_bodies, self.seed);
+        }
+
+        let globals = Globals {
+            softening: self.softening,
+            ..Default::default()
+        };
+
+        // Generate a bunch of bodies
+        let mut rng = Pcg32::seed_from_u64(self.seed);
+        let mut rngify = |x: f32| rng.gen::<f32>() * x;
+
+        let mut bodies = Vec::with_capacity(self.num_bodies + 1);
+        if let Some(mass) = self.central_mass {
+            bodies.push(BodyDescriptor {
+                mass: Some(mass),
+                ..BodyDescriptor::new(Vec2::ZERO, CENTRAL_BODY_RADIUS)
+            });
+        }
+        for _ in 0..self.num_bodies {
+            // Calculate radius
+            let rotation = rngify(2.0 * PI);
+            let radius = rngify(self.max_radius.sqrt());
+
+            // Calculate initial world position as polar coordinates, clear of
+            // any central body
+            let inner = match self.central_mass {
+                Some(_) => CENTRAL_BODY_RADIUS + 2.0 * radius,
+                None => 0.0,
+            };
+            let outer = globals.world_radius;
+            let r = (inner * inner
+                + rngify(1.0) * (outer * outer - inner * inner))
+                .sqrt()
+                - radius;
+            let theta = rngify(2.0 * PI);
+            let position = Mat3::from_rotation_z(theta) * Vec3::X.mul(r);
+
+            // Calculate initial velocity
+            let linvel_theta = rngify(2.0 * PI);
+            let magnitude = rngify(self.max_lin_vel);
+            let linvel =
+                Mat3::from_rotation_z(linvel_theta) * Vec3::X.mul(magnitude);
+
+            // Calculate initial angular velocity
+            let angvel = rngify(self.max_ang_vel);
+
+            bodies.push(BodyDescriptor {
+                velocity: linvel.xy(),
+                rotation,
+                angular_velocity: angvel,
+                ..BodyDescriptor::new(position.xy(), radius)
+            });
+        }
+
+        if let VelocityInit::Orbital { dispersion } = self.velocity {
+            let gravitational_constant =
+                globals.universal_gravity * globals.gravity_amplifier;
+            set_orbital_velocities(
+                &mut bodies,
+                gravitational_constant,
+                dispersion,
+                &mut rng,
+            );
+        }
+
+        Scenario::new(globals, bodies)
+    }
+}
+
+// Mass of a generated body, which leaves rapier to use a density of one
+fn mass(body: &BodyDescriptor) -> f32 {
+    body.mass.unwrap_or(PI * body.radius * body.radius)
+}
+
+// Sends every body counterclockwise around the center of mass, fast enough
+// to balance the pull of the mass closer in, plus a random kick of
+// `dispersion` times that speed
+fn set_orbital_velocities(
+    bodies: &mut [BodyDescriptor],
+    gravitational_constant: f32,
+    dispersion: f32,
+    rng: &mut Pcg32,
+) {
+    let total_mass = bodies.iter().map(mass).sum::<f32>();
+    if total_mass <= 0.0 {
+        return;
+    }
+    let center = bodies
+        .iter()
+        .map(|body| body.position * mass(body))
+        .sum::<Vec2>()
+        / total_mass;
+
+    let mut order = (0..bodies.len()).collect::<Vec<_>>();
+    order.sort_by(|&a, &b| {
+        let distance = |i: usize| bodies[i].position.distance(center);
+        distance(a).total_cmp(&distance(b))
+    });
+
+    let mut enclosed_mass = 0.0;
+    for i in order {
+        let offset = bodies[i].position - center;
+        let distance = offset.length();
+        let speed = match distance > 0.0 {
+            true => (gravitational_constant * enclosed_mass / distance).sqrt(),
+            false => 0.0,
+        };
+        // Gaussian kick in each axis, by the Box-Muller transform
+        let kick = Vec2::from_angle(rng.gen_range(0.0..2.0 * PI))
+            * (-2.0 * rng.gen_range(f32::EPSILON..1.0).ln()).sqrt();
+
+        bodies[i].velocity = offset.normalize_or_zero().perp() * speed
+            + kick * dispersion * speed;
+        enclosed_mass += mass(&bodies[i]);
+    }
+}
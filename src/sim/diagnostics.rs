@@ -0,0 +1,234 @@
+use std::collections::VecDeque;
+
+use glam::Vec2;
+use particular::prelude::Particle;
+
+use crate::sim::barnes_hut::QuadTree;
+use crate::sim::physics::PhysicsContext;
+use crate::sim::GravitySolver;
+
+/// Conserved quantities of the whole system at one instant.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Diagnostics {
+    /// Simulated seconds since the start
+    pub time: f32,
+    /// Translational plus rotational kinetic energy
+    pub kinetic_energy: f32,
+    pub potential_energy: f32,
+    pub momentum: Vec2,
+    /// About the origin, including the spin of every body
+    pub angular_momentum: f32,
+    pub center_of_mass: Vec2,
+}
+
+impl Diagnostics {
+    pub fn measure(physics_context: &PhysicsContext) -> Self {
+        let mut diagnostics = Diagnostics {
+            time: physics_context.time,
+            ..Default::default()
+        };
+
+        let mut total_mass = 0.0;
+        for body in physics_context.bodies() {
+            let rb = &physics_context.rigid_body_set[body.rigidbody_handle];
+            let mass = rb.mass();
+            let inertia = rb.mass_properties().principal_inertia();
+            let position = body.position();
+            let velocity: Vec2 = (*rb.linvel()).into();
+
+            diagnostics.kinetic_energy +=
+                0.5 * mass * velocity.length_squared()
+                    + 0.5 * inertia * rb.angvel().powi(2);
+            diagnostics.momentum += velocity * mass;
+            diagnostics.angular_momentum +=
+                mass * position.perp_dot(velocity) + inertia * rb.angvel();
+            diagnostics.center_of_mass += position * mass;
+            total_mass += mass;
+        }
+        if total_mass > 0.0 {
+            diagnostics.center_of_mass /= total_mass;
+        }
+
+        // With the same solver, `mu` and softening that drive the forces
+        let softening = physics_context.softening;
+        let bodies = physics_context.bodies().collect::<Vec<_>>();
+        match physics_context.gravity_solver {
+            // Every pair once
+            GravitySolver::BruteForce => {
+                for (i, body1) in bodies.iter().enumerate() {
+                    for body2 in &bodies[i + 1..] {
+                        let distance =
+                            body1.position().distance(body2.position());
+                        diagnostics.potential_energy += body2.mass()
+                            * softening.potential(distance, body1.mu());
+                    }
+                }
+            }
+            // Every body in the potential of all the others, which counts
+            // each pair twice
+            GravitySolver::BarnesHut { theta } => {
+                let tree = QuadTree::new(
+                    bodies.iter().map(|body| (body.position(), body.mu())),
+                );
+                diagnostics.potential_energy = 0.5
+                    * bodies
+                        .iter()
+                        .map(|body| {
+                            body.mass()
+                                * tree.potential(
+                                    body.position(),
+                                    theta,
+                                    softening,
+                                )
+                        })
+                        .sum::<f32>();
+            }
+        }
+        diagnostics
+    }
+
+    pub fn total_energy(&self) -> f32 {
+        self.kinetic_energy + self.potential_energy
+    }
+}
+
+/// The most recent diagnostics, oldest first.
+pub struct DiagnosticsHistory {
+    capacity: usize,
+    initial: Option<Diagnostics>,
+    entries: VecDeque<Diagnostics>,
+}
+
+impl DiagnosticsHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            initial: None,
+            entries: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    pub fn push(&mut self, diagnostics: Diagnostics) {
+        self.initial.get_or_insert(diagnostics);
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(diagnostics);
+    }
+
+    /// The first diagnostics ever pushed, kept after it leaves the history.
+    pub fn initial(&self) -> Option<&Diagnostics> {
+        self.initial.as_ref()
+    }
+
+    pub fn latest(&self) -> Option<&Diagnostics> {
+        self.entries.back()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Diagnostics> {
+        self.entries.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Change in total energy since the first push, relative to it.
+    pub fn energy_drift(&self) -> f32 {
+        match (self.initial, self.latest()) {
+            (Some(initial), Some(latest)) => {
+                let initial_energy = initial.total_energy();
+                let change = latest.total_energy() - initial_energy;
+                match initial_energy != 0.0 {
+                    true => change / initial_energy.abs(),
+                    false => change,
+                }
+            }
+            _ => 0.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec2;
+
+    use super::{Diagnostics, DiagnosticsHistory};
+    use crate::sim::{
+        BodyDescriptor, Globals, GravitySolver, Scenario, SimulationConfig,
+    };
+
+    #[test]
+    fn measures_two_bodies() {
+        let globals = Globals {
+            universal_gravity: 1.0,
+            gravity_amplifier: 1.0,
+            ..Default::default()
+        };
+        let bodies = vec![
+            BodyDescriptor {
+                velocity: Vec2::new(0.0, 1.0),
+                angular_velocity: 2.0,
+                mass: Some(2.0),
+                ..BodyDescriptor::new(Vec2::new(-1.0, 0.0), 0.5)
+            },
+            BodyDescriptor {
+                velocity: Vec2::new(0.0, -2.0),
+                mass: Some(1.0),
+                ..BodyDescriptor::new(Vec2::new(2.0, 0.0), 0.5)
+            },
+        ];
+        let physics_context = Scenario::new(globals, bodies).build().unwrap();
+        let diagnostics = Diagnostics::measure(&physics_context);
+
+        let spin = 0.5 * 2.0 * 0.25;
+        let kinetic = 0.5 * 2.0 + 0.5 * 1.0 * 4.0 + 0.5 * spin * 4.0;
+        assert!((diagnostics.kinetic_energy - kinetic).abs() < 1e-4);
+        assert!((diagnostics.potential_energy + 2.0 / 3.0).abs() < 1e-4);
+        assert!(diagnostics.momentum.length() < 1e-4);
+        let angular = -2.0 + 1.0 * 2.0 * -2.0 + spin * 2.0;
+        assert!((diagnostics.angular_momentum - angular).abs() < 1e-4);
+        assert!(diagnostics.center_of_mass.length() < 1e-4);
+    }
+
+    #[test]
+    fn barnes_hut_potential_matches_pair_sum() {
+        let mut physics_context = SimulationConfig {
+            num_bodies: 300,
+            ..Default::default()
+        }
+        .generate()
+        .build()
+        .unwrap();
+        let exact = Diagnostics::measure(&physics_context).potential_energy;
+        physics_context.gravity_solver =
+            GravitySolver::BarnesHut { theta: 0.5 };
+        let approx = Diagnostics::measure(&physics_context).potential_energy;
+        assert!(
+            (approx - exact).abs() < 0.01 * exact.abs(),
+            "{} vs {}",
+            approx,
+            exact
+        );
+    }
+
+    #[test]
+    fn history_keeps_the_latest_entries() {
+        let mut history = DiagnosticsHistory::new(3);
+        for i in 0..5 {
+            history.push(Diagnostics {
+                time: i as f32,
+                kinetic_energy: 1.0 + i as f32,
+                ..Default::default()
+            });
+        }
+        let times = history.iter().map(|d| d.time).collect::<Vec<_>>();
+        assert_eq!(times, vec![2.0, 3.0, 4.0]);
+        assert_eq!(history.initial().unwrap().time, 0.0);
+        assert_eq!(history.energy_drift(), 4.0);
+    }
+}
@@ -0,0 +1,260 @@
+use glam::Vec2;
+use particular::prelude::Particle;
+
+use crate::sim::physics::PhysicsContext;
+use crate::sim::{
+    Colormap, Softening, FIELD_CONTOURS, FIELD_MAX_RESOLUTION, FIELD_RESOLUTION,
+};
+
+/// What the background field shows.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FieldQuantity {
+    /// Gravitational potential per unit mass
+    #[default]
+    Potential,
+    /// Magnitude of the gravitational acceleration
+    Strength,
+}
+
+impl FieldQuantity {
+    /// Identifies the quantity to the field shaders.
+    pub fn shader_mode(&self) -> u32 {
+        match self {
+            FieldQuantity::Potential => 0,
+            FieldQuantity::Strength => 1,
+        }
+    }
+}
+
+/// A heatmap of the gravitational field drawn under the bodies, sampled on a
+/// grid across the screen. The shaders evaluate it on the GPU; the sampling
+/// here is the reference they follow.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FieldOverlay {
+    pub enabled: bool,
+    pub quantity: FieldQuantity,
+    /// Equipotential lines, or lines of equal strength
+    pub contours: bool,
+    pub colormap: Colormap,
+    /// Grid cells along each side of the screen
+    resolution: u32,
+}
+
+impl Default for FieldOverlay {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            quantity: FieldQuantity::Potential,
+            contours: true,
+            colormap: Colormap::Magma,
+            resolution: FIELD_RESOLUTION,
+        }
+    }
+}
+
+impl FieldOverlay {
+    pub fn resolution(&self) -> u32 {
+        self.resolution
+    }
+
+    pub fn set_resolution(&mut self, resolution: u32) {
+        self.resolution = resolution.clamp(1, FIELD_MAX_RESOLUTION);
+    }
+
+    /// Steps from off, to potential, to strength and back off.
+    pub fn cycle(&mut self) {
+        match (self.enabled, self.quantity) {
+            (false, _) => {
+                self.enabled = true;
+                self.quantity = FieldQuantity::Potential;
+            }
+            (true, FieldQuantity::Potential) => {
+                self.quantity = FieldQuantity::Strength
+            }
+            (true, FieldQuantity::Strength) => self.enabled = false,
+        }
+    }
+
+    /// Contour levels across the colormap, or none.
+    pub fn contour_levels(&self) -> u32 {
+        match self.contours {
+            true => FIELD_CONTOURS,
+            false => 0,
+        }
+    }
+
+    /// The field of `sources`, given as positions and `mu`, at `at`.
+    pub fn sample(
+        &self,
+        sources: &[(Vec2, f32)],
+        softening: Softening,
+        at: Vec2,
+    ) -> f32 {
+        match self.quantity {
+            FieldQuantity::Potential => sources
+                .iter()
+                .map(|&(position, mu)| {
+                    softening.potential(position.distance(at), mu)
+                })
+                .sum(),
+            FieldQuantity::Strength => sources
+                .iter()
+                .map(|&(position, mu)| {
+                    softening.acceleration(position - at, mu)
+                })
+                .sum::<Vec2>()
+                .length(),
+        }
+    }
+
+    /// A typical magnitude of the field: that of all the mass at the world
+    /// center, seen from the edge.
+    pub fn scale(&self, physics_context: &PhysicsContext) -> f32 {
+        let mu = physics_context.bodies().map(|body| body.mu()).sum::<f32>();
+        let radius = physics_context.world_radius;
+        let scale = match self.quantity {
+            FieldQuantity::Potential => mu / radius,
+            FieldQuantity::Strength => mu / (radius * radius),
+        };
+        match scale > 0.0 {
+            true => scale,
+            false => 1.0,
+        }
+    }
+
+    /// Maps a field value into `[0, 1)`, reaching one half at `scale`.
+    pub fn shade(value: f32, scale: f32) -> f32 {
+        let magnitude = value.abs();
+        magnitude / (magnitude + scale)
+    }
+
+    /// The field at the corners of a `resolution` by `resolution` grid
+    /// spanning `min` to `max`, one row at a time starting from `min`.
+    pub fn grid(
+        &self,
+        physics_context: &PhysicsContext,
+        min: Vec2,
+        max: Vec2,
+    ) -> Vec<f32> {
+        let sources = physics_context
+            .bodies()
+            .map(|body| (body.position(), body.mu()))
+            .collect::<Vec<_>>();
+        let cells = self.resolution as f32;
+        let corners = self.resolution + 1;
+        (0..corners)
+            .flat_map(|y| (0..corners).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                let t = Vec2::new(x as f32, y as f32) / cells;
+                let at = min + (max - min) * t;
+                self.sample(&sources, physics_context.softening, at)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec2;
+
+    use super::{FieldOverlay, FieldQuantity};
+    use crate::sim::{BodyDescriptor, Globals, Scenario, Softening};
+
+    fn strength() -> FieldOverlay {
+        FieldOverlay {
+            quantity: FieldQuantity::Strength,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn follows_the_inverse_law() {
+        let sources = [(Vec2::new(1.0, 2.0), 8.0)];
+        let at = Vec2::new(5.0, 2.0);
+        let potential =
+            FieldOverlay::default().sample(&sources, Softening::None, at);
+        assert!((potential + 2.0).abs() < 1e-6, "{}", potential);
+        let strength = strength().sample(&sources, Softening::None, at);
+        assert!((strength - 0.5).abs() < 1e-6, "{}", strength);
+
+        // Softening keeps the field finite on top of a body
+        let soft = Softening::Plummer { length: 0.5 };
+        let center =
+            FieldOverlay::default().sample(&sources, soft, sources[0].0);
+        assert!((center + 16.0).abs() < 1e-5, "{}", center);
+    }
+
+    #[test]
+    fn samples_a_symmetric_pair() {
+        let bodies = vec![
+            BodyDescriptor {
+                mass: Some(5.0),
+                ..BodyDescriptor::new(Vec2::new(-10.0, 0.0), 0.5)
+            },
+            BodyDescriptor {
+                mass: Some(5.0),
+                ..BodyDescriptor::new(Vec2::new(10.0, 0.0), 0.5)
+            },
+        ];
+        let physics_context =
+            Scenario::new(Globals::default(), bodies).build().unwrap();
+        let (min, max) = (Vec2::splat(-25.0), Vec2::splat(25.0));
+
+        let mut overlay = FieldOverlay::default();
+        overlay.set_resolution(4);
+        let grid = overlay.grid(&physics_context, min, max);
+        assert_eq!(grid.len(), 25);
+        // Mirrored across both axes, and deeper inside than out
+        for y in 0..5 {
+            for x in 0..5 {
+                let value = grid[y * 5 + x];
+                assert!((value - grid[y * 5 + 4 - x]).abs() < 1e-4);
+                assert!((value - grid[(4 - y) * 5 + x]).abs() < 1e-4);
+                assert!(value < 0.0);
+            }
+        }
+        assert!(grid[12] < grid[0] && grid[12] < grid[2]);
+
+        // The pulls cancel out halfway between
+        let mut overlay = strength();
+        overlay.set_resolution(2);
+        let (min, max) = (Vec2::splat(-20.0), Vec2::splat(20.0));
+        let grid = overlay.grid(&physics_context, min, max);
+        assert!(grid[4] < 1e-6, "{}", grid[4]);
+        assert!(grid[3] > 0.0);
+    }
+
+    #[test]
+    fn shades_into_the_unit_range() {
+        let scale = 2.0;
+        let shades = [0.0, -1.0, -2.0, -50.0, -1e9]
+            .map(|value| FieldOverlay::shade(value, scale));
+        assert_eq!(shades[0], 0.0);
+        assert_eq!(shades[2], 0.5);
+        assert!(shades.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(shades[4] <= 1.0);
+    }
+
+    #[test]
+    fn cycles_and_clamps() {
+        let mut overlay = FieldOverlay::default();
+        let mut seen = vec![];
+        for _ in 0..3 {
+            overlay.cycle();
+            seen.push((overlay.enabled, overlay.quantity));
+        }
+        assert_eq!(
+            seen,
+            [
+                (true, FieldQuantity::Potential),
+                (true, FieldQuantity::Strength),
+                (false, FieldQuantity::Strength),
+            ]
+        );
+
+        overlay.set_resolution(0);
+        assert_eq!(overlay.resolution(), 1);
+        overlay.set_resolution(100_000);
+        assert!(overlay.resolution() < 100_000);
+    }
+}
@@ -1,15 +1,21 @@
 use std::collections::HashSet;
-use winit::event::VirtualKeyCode;
+use winit::event::{MouseButton, VirtualKeyCode};
+
+use crate::sim::{Action, ActionMap, Binding, Trigger};
 
 #[derive(Default)]
 pub struct InputController {
     pub keys: HashSet<VirtualKeyCode>,
     pub keys_last: HashSet<VirtualKeyCode>,
+    pub buttons: HashSet<MouseButton>,
+    pub buttons_last: HashSet<MouseButton>,
+    pub actions: ActionMap,
 }
 
 impl InputController {
     pub fn update(&mut self) {
         self.keys_last = self.keys.clone();
+        self.buttons_last = self.buttons.clone();
     }
     pub fn press(&mut self, key: VirtualKeyCode) {
         self.keys.insert(key);
@@ -34,4 +40,69 @@ impl InputController {
     pub fn is_key_released(&self, key: VirtualKeyCode) -> bool {
         !self.keys.contains(&key) && self.keys_last.contains(&key)
     }
+    pub fn press_button(&mut self, button: MouseButton) {
+        self.buttons.insert(button);
+    }
+    pub fn release_button(&mut self, button: MouseButton) {
+        self.buttons.remove(&button);
+    }
+    pub fn is_button_active(&self, button: MouseButton) -> bool {
+        self.buttons.contains(&button)
+    }
+    pub fn is_button_pressed(&self, button: MouseButton) -> bool {
+        self.buttons.contains(&button) && !self.buttons_last.contains(&button)
+    }
+    pub fn is_button_released(&self, button: MouseButton) -> bool {
+        !self.buttons.contains(&button) && self.buttons_last.contains(&button)
+    }
+    pub fn is_action_active(&self, action: Action) -> bool {
+        self.actions.bindings(action).iter().any(|binding| {
+            self.is_trigger_active(binding.trigger)
+                && self.modifiers_match(binding)
+        })
+    }
+    pub fn is_one_of_action_active(&self, actions: Vec<Action>) -> bool {
+        actions
+            .into_iter()
+            .any(|action| self.is_action_active(action))
+    }
+    pub fn is_action_pressed(&self, action: Action) -> bool {
+        self.actions.bindings(action).iter().any(|binding| {
+            let trigger = binding.trigger;
+            self.is_trigger_active(trigger)
+                && !self.was_trigger_active(trigger)
+                && self.modifiers_match(binding)
+        })
+    }
+    // Modifiers may be let go first, so they aren't needed to release
+    pub fn is_action_released(&self, action: Action) -> bool {
+        self.actions.bindings(action).iter().any(|binding| {
+            !self.is_trigger_active(binding.trigger)
+                && self.was_trigger_active(binding.trigger)
+        })
+    }
+    fn is_trigger_active(&self, trigger: Trigger) -> bool {
+        match trigger {
+            Trigger::Key(key) => self.keys.contains(&key),
+            Trigger::Mouse(button) => self.buttons.contains(&button),
+        }
+    }
+    fn was_trigger_active(&self, trigger: Trigger) -> bool {
+        match trigger {
+            Trigger::Key(key) => self.keys_last.contains(&key),
+            Trigger::Mouse(button) => self.buttons_last.contains(&button),
+        }
+    }
+    // A modifier bound as the trigger itself doesn't count as held
+    fn modifiers_match(&self, binding: &Binding) -> bool {
+        let held = |left, right| {
+            [left, right].into_iter().any(|key| {
+                binding.trigger != Trigger::Key(key) && self.keys.contains(&key)
+            })
+        };
+        (!binding.shift || held(VirtualKeyCode::LShift, VirtualKeyCode::RShift))
+            && binding.ctrl
+                == held(VirtualKeyCode::LControl, VirtualKeyCode::RControl)
+            && binding.alt == held(VirtualKeyCode::LAlt, VirtualKeyCode::RAlt)
+    }
 }
@@ -0,0 +1,120 @@
+use glam::Vec2;
+
+use crate::sim::physics::PhysicsContext;
+use crate::sim::BodyId;
+
+/// A live readout of a single body.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Inspection {
+    pub id: BodyId,
+    pub mass: f32,
+    pub radius: f32,
+    pub position: Vec2,
+    pub velocity: Vec2,
+    pub angular_velocity: f32,
+    /// Translational plus rotational
+    pub kinetic_energy: f32,
+    /// The closest other body and the distance between their centers
+    pub nearest_neighbor: Option<(BodyId, f32)>,
+}
+
+impl Inspection {
+    pub fn measure(
+        physics_context: &PhysicsContext,
+        id: BodyId,
+    ) -> Option<Self> {
+        let body = physics_context.body(id)?;
+        let rb = &physics_context.rigid_body_set[body.rigidbody_handle];
+        let velocity: Vec2 = (*rb.linvel()).into();
+        let inertia = rb.mass_properties().principal_inertia();
+
+        let nearest_neighbor = physics_context
+            .bodies()
+            .filter(|other| other.id() != id)
+            .map(|other| {
+                (other.id(), other.position().distance(body.position()))
+            })
+            .min_by(|(_, a), (_, b)| a.total_cmp(b));
+
+        Some(Self {
+            id,
+            mass: rb.mass(),
+            radius: body.radius(),
+            position: body.position(),
+            velocity,
+            angular_velocity: rb.angvel(),
+            kinetic_energy: 0.5 * rb.mass() * velocity.length_squared()
+                + 0.5 * inertia * rb.angvel().powi(2),
+            nearest_neighbor,
+        })
+    }
+}
+
+impl PhysicsContext {
+    /// The body covering `point`, preferring the one whose center is
+    /// closest.
+    pub fn pick(&self, point: Vec2) -> Option<BodyId> {
+        self.bodies()
+            .map(|body| (body, body.position().distance(point)))
+            .filter(|(body, distance)| *distance <= body.radius())
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(body, _)| body.id())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec2;
+
+    use super::Inspection;
+    use crate::sim::{BodyDescriptor, Globals, Scenario};
+
+    fn scenario() -> Scenario {
+        let bodies = vec![
+            BodyDescriptor {
+                velocity: Vec2::new(3.0, 4.0),
+                angular_velocity: 2.0,
+                mass: Some(2.0),
+                ..BodyDescriptor::new(Vec2::ZERO, 1.0)
+            },
+            BodyDescriptor::new(Vec2::new(1.5, 0.0), 1.0),
+            BodyDescriptor::new(Vec2::new(10.0, 0.0), 0.5),
+        ];
+        Scenario::new(Globals::default(), bodies)
+    }
+
+    #[test]
+    fn picks_the_closest_body_under_the_point() {
+        let physics_context = scenario().build().unwrap();
+        let ids = physics_context
+            .bodies()
+            .map(|body| body.id())
+            .collect::<Vec<_>>();
+        // Inside both of the overlapping bodies, but nearer the second
+        assert_eq!(physics_context.pick(Vec2::new(0.9, 0.0)), Some(ids[1]));
+        assert_eq!(physics_context.pick(Vec2::new(-0.5, 0.5)), Some(ids[0]));
+        assert_eq!(physics_context.pick(Vec2::new(10.0, 0.4)), Some(ids[2]));
+        assert_eq!(physics_context.pick(Vec2::new(5.0, 0.0)), None);
+    }
+
+    #[test]
+    fn inspects_a_body() {
+        let physics_context = scenario().build().unwrap();
+        let ids = physics_context
+            .bodies()
+            .map(|body| body.id())
+            .collect::<Vec<_>>();
+        let inspection = Inspection::measure(&physics_context, ids[0]).unwrap();
+
+        assert_eq!(inspection.velocity, Vec2::new(3.0, 4.0));
+        assert!((inspection.mass - 2.0).abs() < 1e-5);
+        // Half m v² plus half of the disc's ½ m r² times ω²
+        let kinetic = 0.5 * 2.0 * 25.0 + 0.5 * (0.5 * 2.0) * 4.0;
+        assert!((inspection.kinetic_energy - kinetic).abs() < 1e-4);
+        assert_eq!(inspection.nearest_neighbor, Some((ids[1], 1.5)));
+
+        let mut physics_context = physics_context;
+        physics_context.despawn(ids[0]);
+        assert!(Inspection::measure(&physics_context, ids[0]).is_none());
+    }
+}
@@ -0,0 +1,314 @@
+use glam::Vec2;
+use serde::{Deserialize, Serialize};
+
+/// Accelerations felt at each of a set of positions.
+pub type AccelerationFn<'a> = dyn FnMut(&[Vec2]) -> Vec<Vec2> + 'a;
+
+/// Advances point masses under gravity, without contacts.
+pub trait Integrator {
+    /// Moves `positions` and `velocities` forward by `dt`.
+    fn step(
+        &self,
+        positions: &mut [Vec2],
+        velocities: &mut [Vec2],
+        dt: f32,
+        accelerations: &mut AccelerationFn,
+    );
+}
+
+/// How bodies are moved each step.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum Integration {
+    /// Rapier's pipeline, which also resolves contacts
+    #[default]
+    Rapier,
+    /// Gravity only, integrated directly and ignoring contacts
+    Collisionless(IntegratorKind),
+}
+
+impl Integration {
+    /// Whether stepping backwards retraces the path exactly, up to round off.
+    pub fn is_time_reversible(&self) -> bool {
+        matches!(
+            self,
+            Integration::Collisionless(
+                IntegratorKind::Leapfrog | IntegratorKind::Yoshida4
+            )
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum IntegratorKind {
+    Leapfrog,
+    Yoshida4,
+    RungeKutta4,
+}
+
+impl IntegratorKind {
+    pub fn integrator(&self) -> &'static dyn Integrator {
+        match self {
+            IntegratorKind::Leapfrog => &Leapfrog,
+            IntegratorKind::Yoshida4 => &Yoshida4,
+            IntegratorKind::RungeKutta4 => &RungeKutta4,
+        }
+    }
+}
+
+fn drift(positions: &mut [Vec2], velocities: &[Vec2], dt: f32) {
+    for (position, velocity) in positions.iter_mut().zip(velocities) {
+        *position += *velocity * dt;
+    }
+}
+
+fn kick(velocities: &mut [Vec2], accelerations: &[Vec2], dt: f32) {
+    for (velocity, acceleration) in velocities.iter_mut().zip(accelerations) {
+        *velocity += *acceleration * dt;
+    }
+}
+
+/// Second order symplectic drift-kick-drift leapfrog, equivalent to
+/// velocity Verlet. One force evaluation per step.
+pub struct Leapfrog;
+
+impl Integrator for Leapfrog {
+    fn step(
+        &self,
+        positions: &mut [Vec2],
+        velocities: &mut [Vec2],
+        dt: f32,
+        accelerations: &mut AccelerationFn,
+    ) {
+        drift(positions, velocities, 0.5 * dt);
+        kick(velocities, &accelerations(positions), dt);
+        drift(positions, velocities, 0.5 * dt);
+    }
+}
+
+/// Yoshida's fourth order symplectic composition of three leapfrog steps.
+/// Three force evaluations per step.
+pub struct Yoshida4;
+
+impl Integrator for Yoshida4 {
+    fn step(
+        &self,
+        positions: &mut [Vec2],
+        velocities: &mut [Vec2],
+        dt: f32,
+        accelerations: &mut AccelerationFn,
+    ) {
+        let cbrt_2 = 2f32.powf(1.0 / 3.0);
+        let w1 = 1.0 / (2.0 - cbrt_2);
+        let w0 = -cbrt_2 * w1;
+        let drifts = [0.5 * w1, 0.5 * (w0 + w1), 0.5 * (w0 + w1), 0.5 * w1];
+        let kicks = [w1, w0, w1];
+
+        for (drift_weight, kick_weight) in drifts.iter().zip(kicks) {
+            drift(positions, velocities, drift_weight * dt);
+            kick(velocities, &accelerations(positions), kick_weight * dt);
+        }
+        drift(positions, velocities, drifts[3] * dt);
+    }
+}
+
+/// Classic fourth order Runge-Kutta. Accurate per step, but not symplectic,
+/// so energy slowly drifts. Four force evaluations per step.
+pub struct RungeKutta4;
+
+impl Integrator for RungeKutta4 {
+    fn step(
+        &self,
+        positions: &mut [Vec2],
+        velocities: &mut [Vec2],
+        dt: f32,
+        accelerations: &mut AccelerationFn,
+    ) {
+        let offset = |base: &[Vec2], slope: &[Vec2], h: f32| {
+            base.iter()
+                .zip(slope)
+                .map(|(b, s)| *b + *s * h)
+                .collect::<Vec<_>>()
+        };
+
+        let k1_x = velocities.to_vec();
+        let k1_v = accelerations(positions);
+        let k2_x = offset(velocities, &k1_v, 0.5 * dt);
+        let k2_v = accelerations(&offset(positions, &k1_x, 0.5 * dt));
+        let k3_x = offset(velocities, &k2_v, 0.5 * dt);
+        let k3_v = accelerations(&offset(positions, &k2_x, 0.5 * dt));
+        let k4_x = offset(velocities, &k3_v, dt);
+        let k4_v = accelerations(&offset(positions, &k3_x, dt));
+
+        for i in 0..positions.len() {
+            positions[i] +=
+                (k1_x[i] + 2.0 * k2_x[i] + 2.0 * k3_x[i] + k4_x[i]) * dt / 6.0;
+            velocities[i] +=
+                (k1_v[i] + 2.0 * k2_v[i] + 2.0 * k3_v[i] + k4_v[i]) * dt / 6.0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use glam::Vec2;
+
+    use super::{Integration, IntegratorKind};
+    use crate::sim::physics::PhysicsContext;
+    use crate::sim::softening::accelerations;
+    use crate::sim::{presets, BodyDescriptor, Globals, Scenario, Softening};
+
+    const MUS: [f32; 2] = [1.0, 0.5];
+    const STEPS_PER_PERIOD: usize = 200;
+
+    fn energy(positions: &[Vec2], velocities: &[Vec2]) -> f32 {
+        let kinetic = 0.5 * MUS[0] * velocities[0].length_squared()
+            + 0.5 * MUS[1] * velocities[1].length_squared();
+        kinetic - MUS[0] * MUS[1] / positions[0].distance(positions[1])
+    }
+
+    // Largest relative energy error over `periods` of an eccentric two body
+    // orbit, for the first and the last tenth of the run
+    fn kepler_energy_error(kind: IntegratorKind, periods: usize) -> (f32, f32) {
+        // Relative orbit with semi-major axis 1 and eccentricity 0.5, started
+        // at apocenter, in the center of mass frame
+        let (a, e) = (1.0f32, 0.5f32);
+        let mu = MUS[0] + MUS[1];
+        let apocenter = a * (1.0 + e);
+        let speed = (mu / a * (1.0 - e) / (1.0 + e)).sqrt();
+        let mut positions = [
+            Vec2::new(-apocenter * MUS[1] / mu, 0.0),
+            Vec2::new(apocenter * MUS[0] / mu, 0.0),
+        ];
+        let mut velocities = [
+            Vec2::new(0.0, -speed * MUS[1] / mu),
+            Vec2::new(0.0, speed * MUS[0] / mu),
+        ];
+
+        let period = 2.0 * PI * (a.powi(3) / mu).sqrt();
+        let dt = period / STEPS_PER_PERIOD as f32;
+        let initial = energy(&positions, &velocities);
+        let mut gravity = |positions: &[Vec2]| {
+            let points = positions
+                .iter()
+                .zip(MUS)
+                .map(|(position, mu)| (*position, mu))
+                .collect::<Vec<_>>();
+            accelerations(&points, Softening::None)
+        };
+
+        let steps = periods * STEPS_PER_PERIOD;
+        let (mut early, mut late) = (0.0f32, 0.0f32);
+        for step in 0..steps {
+            kind.integrator().step(
+                &mut positions,
+                &mut velocities,
+                dt,
+                &mut gravity,
+            );
+            let error =
+                ((energy(&positions, &velocities) - initial) / initial).abs();
+            if step < steps / 10 {
+                early = early.max(error);
+            } else if step >= steps - steps / 10 {
+                late = late.max(error);
+            }
+        }
+        (early, late)
+    }
+
+    #[test]
+    fn leapfrog_energy_error_is_bounded() {
+        let (early, late) = kepler_energy_error(IntegratorKind::Leapfrog, 2000);
+        assert!(early < 5e-3, "early error {}", early);
+        assert!(late < 2.0 * early, "error grew from {} to {}", early, late);
+    }
+
+    #[test]
+    fn yoshida_energy_error_is_bounded() {
+        let (early, late) = kepler_energy_error(IntegratorKind::Yoshida4, 2000);
+        assert!(early < 1e-4, "early error {}", early);
+        assert!(late < 1e-4, "late error {}", late);
+    }
+
+    #[test]
+    fn runge_kutta_energy_error_stays_small() {
+        let (early, late) =
+            kepler_energy_error(IntegratorKind::RungeKutta4, 2000);
+        // Not symplectic, so the error creeps up, but slowly
+        assert!(early < 1e-3, "early error {}", early);
+        assert!(late < 1e-2, "late error {}", late);
+    }
+
+    #[test]
+    fn collisionless_bodies_pass_through_each_other() {
+        let globals = Globals {
+            universal_gravity: 0.0,
+            integration: Integration::Collisionless(IntegratorKind::Leapfrog),
+            ..Default::default()
+        };
+        let bodies = vec![
+            BodyDescriptor {
+                velocity: Vec2::new(2.0, 0.0),
+                ..BodyDescriptor::new(Vec2::new(-2.0, 0.0), 0.5)
+            },
+            BodyDescriptor {
+                velocity: Vec2::new(-2.0, 0.0),
+                ..BodyDescriptor::new(Vec2::new(2.0, 0.0), 0.5)
+            },
+        ];
+        let mut physics_context =
+            Scenario::new(globals, bodies).build().unwrap();
+        for _ in 0..120 {
+            physics_context.step();
+        }
+        let xs = physics_context
+            .bodies()
+            .map(|body| body.position().x)
+            .collect::<Vec<_>>();
+        assert!((xs[0] - 2.0).abs() < 1e-3, "{:?}", xs);
+        assert!((xs[1] + 2.0).abs() < 1e-3, "{:?}", xs);
+    }
+
+    #[test]
+    fn reversed_time_retraces_the_orbit() {
+        let mut scenario = presets::binary(0.5);
+        scenario.globals.integration =
+            Integration::Collisionless(IntegratorKind::Leapfrog);
+        let mut physics_context = scenario.build().unwrap();
+        let positions = |physics_context: &PhysicsContext| {
+            physics_context
+                .bodies()
+                .map(|body| body.position())
+                .collect::<Vec<_>>()
+        };
+        let start = positions(&physics_context);
+
+        for _ in 0..300 {
+            physics_context.step();
+        }
+        assert!(physics_context.set_reversed(true));
+        for _ in 0..300 {
+            physics_context.step();
+        }
+        assert!(
+            physics_context.time.abs() < 1e-3,
+            "{}",
+            physics_context.time
+        );
+        for (a, b) in start.iter().zip(positions(&physics_context)) {
+            assert!(a.distance(b) < 1e-2, "{} != {}", a, b);
+        }
+
+        // Rapier can't run backwards
+        physics_context.set_integration(Integration::Rapier);
+        assert!(!physics_context.is_reversed());
+        assert!(!physics_context.set_reversed(true));
+        // Nor does it pick up running backwards again afterwards
+        physics_context.set_integration(Integration::Collisionless(
+            IntegratorKind::Leapfrog,
+        ));
+        assert!(!physics_context.is_reversed());
+    }
+}
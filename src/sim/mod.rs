@@ -1,11 +1,70 @@
 mod body;
+pub use body::{Body, BodyId};
 
 mod state;
 pub use state::State;
 
 pub mod input;
 
+mod action;
+pub use action::{Action, ActionMap, Binding, Trigger};
+
+mod camera_target;
+pub use camera_target::CameraTarget;
+
+mod coloring;
+pub use coloring::{ColorAttribute, ColorRange, Coloring, Colormap};
+
+mod field;
+pub use field::{FieldOverlay, FieldQuantity};
+
+mod inspector;
+pub use inspector::Inspection;
+
+mod slingshot;
+pub use slingshot::Slingshot;
+
 mod simulation;
 pub use simulation::*;
 
+mod config;
+pub use config::{SimulationConfig, VelocityInit};
+
+mod scenario;
+pub use scenario::{
+    BodyDescriptor, Globals, Scenario, ScenarioError, SCENARIO_VERSION,
+};
+
+pub mod presets;
+pub use presets::Preset;
+
 mod physics;
+pub use physics::GravitySolver;
+
+mod barnes_hut;
+
+mod boundary;
+pub use boundary::BoundaryPolicy;
+
+mod softening;
+pub use softening::Softening;
+
+mod integrator;
+pub use integrator::{
+    Integration, Integrator, IntegratorKind, Leapfrog, RungeKutta4, Yoshida4,
+};
+
+mod trails;
+pub use trails::Trails;
+
+mod diagnostics;
+pub use diagnostics::{Diagnostics, DiagnosticsHistory};
+
+mod collision;
+pub use collision::{merge, CollisionMode, DensityModel};
+
+mod timestep;
+pub use timestep::FixedTimestep;
+
+mod snapshot;
+pub use snapshot::{CameraSnapshot, PhysicsSnapshot, Snapshot};
@@ -1,10 +1,39 @@
+use std::collections::{HashMap, HashSet};
+
+use glam::Vec2;
+use particular::prelude::Particle;
 use particular::ParticleSet;
 use rapier2d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::barnes_hut::QuadTree;
+use super::body::{Body, BodyId};
+use super::boundary::{wall_collider, wrap_position};
+use super::collision::{merge, merge_groups};
+use super::softening::accelerations;
+use crate::sim::{
+    BodyDescriptor, BoundaryPolicy, CollisionMode, DensityModel, Integration,
+    Integrator, Softening, GRAVITY_AMPLIFIER, UNIVERSAL_GRAVITY, WORLD_RADIUS,
+};
 
-use super::body::Body;
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum GravitySolver {
+    /// Exact all-pairs sum, O(n²)
+    BruteForce,
+    /// Barnes-Hut quadtree with opening angle `theta`, O(n log n)
+    BarnesHut { theta: f32 },
+}
 
 pub struct PhysicsContext {
-    pub bodies: ParticleSet<Body>,
+    bodies: ParticleSet<Body>,
+    pub gravity_solver: GravitySolver,
+    pub collision_mode: CollisionMode,
+    pub softening: Softening,
+    integration: Integration,
+    pub gravitational_constant: f32,
+    pub world_radius: f32,
+    /// Simulated seconds since the start
+    pub time: f32,
     pub integration_parameters: IntegrationParameters,
     pub physics_pipeline: PhysicsPipeline,
     pub island_manager: IslandManager,
@@ -13,12 +42,27 @@ pub struct PhysicsContext {
     pub ccd_solver: CCDSolver,
     pub rigid_body_set: RigidBodySet,
     pub collider_set: ColliderSet,
+    pub impulse_joint_set: ImpulseJointSet,
+    pub multibody_joint_set: MultibodyJointSet,
+    boundary: BoundaryPolicy,
+    wall_handle: Option<ColliderHandle>,
+    next_body_id: u64,
+    reversed: bool,
+    // Bodies in contact after the last rapier step
+    touching: HashSet<(RigidBodyHandle, RigidBodyHandle)>,
 }
 
 impl PhysicsContext {
     pub fn new() -> Self {
-        Self {
+        let mut physics_context = Self {
             bodies: ParticleSet::<Body>::new(),
+            gravity_solver: GravitySolver::BruteForce,
+            collision_mode: CollisionMode::default(),
+            softening: Softening::default(),
+            integration: Integration::default(),
+            gravitational_constant: UNIVERSAL_GRAVITY * GRAVITY_AMPLIFIER,
+            world_radius: WORLD_RADIUS,
+            time: 0.0,
             integration_parameters: IntegrationParameters::default(),
             physics_pipeline: PhysicsPipeline::new(),
             island_manager: IslandManager::new(),
@@ -27,33 +71,198 @@ impl PhysicsContext {
             ccd_solver: CCDSolver::new(),
             rigid_body_set: RigidBodySet::new(),
             collider_set: ColliderSet::new(),
+            impulse_joint_set: ImpulseJointSet::new(),
+            multibody_joint_set: MultibodyJointSet::new(),
+            boundary: BoundaryPolicy::Open,
+            wall_handle: None,
+            next_body_id: 0,
+            reversed: false,
+            touching: HashSet::new(),
+        };
+        physics_context.set_boundary(BoundaryPolicy::default());
+        physics_context
+    }
+
+    pub fn boundary(&self) -> BoundaryPolicy {
+        self.boundary
+    }
+
+    /// Switches the boundary policy, rebuilding the wall at the current
+    /// `world_radius` if there is one.
+    pub fn set_boundary(&mut self, boundary: BoundaryPolicy) {
+        if let Some(handle) = self.wall_handle.take() {
+            self.collider_set.remove(
+                handle,
+                &mut self.island_manager,
+                &mut self.rigid_body_set,
+                false,
+            );
+        }
+        if let BoundaryPolicy::Wall { restitution } = boundary {
+            let wall = wall_collider(self.world_radius, restitution);
+            self.wall_handle = Some(self.collider_set.insert(wall));
         }
+        self.boundary = boundary;
     }
 
+    /// Adds a body built from `body`, keeping its id if it has one. That id
+    /// must not belong to another body.
+    pub fn spawn(&mut self, body: &BodyDescriptor) -> BodyId {
+        let (rb, coll) = body.build();
+        let id = body.id.unwrap_or(BodyId(self.next_body_id));
+        // Ids from `next_body_id` on are never in use
+        debug_assert!(
+            id.0 >= self.next_body_id || self.body(id).is_none(),
+            "{:?} is already in use",
+            id
+        );
+        self.insert_body(id, rb, coll)
+    }
+
+    /// Adds a body made of a rigid body and its collider, which must be a
+    /// ball.
     pub fn create_body(
         &mut self,
         rb: impl Into<RigidBody>,
         coll: impl Into<Collider>,
-    ) {
+    ) -> BodyId {
+        self.insert_body(BodyId(self.next_body_id), rb, coll)
+    }
+
+    fn insert_body(
+        &mut self,
+        id: BodyId,
+        rb: impl Into<RigidBody>,
+        coll: impl Into<Collider>,
+    ) -> BodyId {
         let rigid_body_handle = self.rigid_body_set.insert(rb);
         let collider_handle = self.collider_set.insert_with_parent(
-            coll,
+            coll.into(),
             rigid_body_handle,
             &mut self.rigid_body_set,
         );
 
-        let mut particle = Body::new(rigid_body_handle, collider_handle);
+        self.next_body_id = self.next_body_id.max(id.0 + 1);
+        let mut particle = Body::new(
+            id,
+            rigid_body_handle,
+            collider_handle,
+            self.gravitational_constant,
+        );
         particle.sync_to_rigidbody(&self.rigid_body_set, &self.collider_set);
+        particle.save_previous();
 
         self.bodies.add(particle);
+        id
+    }
+
+    /// Removes a body, along with its collider and any joints attached to
+    /// it. Returns whether the body existed.
+    pub fn despawn(&mut self, id: BodyId) -> bool {
+        self.remove_bodies(|body| body.id() == id) > 0
+    }
+
+    /// Looks up a body by id, in O(n).
+    pub fn body(&self, id: BodyId) -> Option<&Body> {
+        self.bodies.iter().find(|body| body.id() == id)
+    }
+
+    /// Every body, massive ones first.
+    pub fn bodies(&self) -> impl Iterator<Item = &Body> {
+        self.bodies.iter()
+    }
+
+    pub(crate) fn bodies_mut(&mut self) -> impl Iterator<Item = &mut Body> {
+        self.bodies.iter_mut()
+    }
+
+    /// The id the next body without one will get.
+    pub fn next_body_id(&self) -> BodyId {
+        BodyId(self.next_body_id)
+    }
+
+    /// Skips the ids before `id`, so the next body gets `id` or later.
+    pub fn reserve_body_ids(&mut self, id: BodyId) {
+        self.next_body_id = self.next_body_id.max(id.0);
+    }
+
+    pub fn body_count(&self) -> usize {
+        self.bodies.iter().count()
+    }
+
+    pub fn integration(&self) -> Integration {
+        self.integration
+    }
+
+    /// Switches how bodies are moved, going forward in time again if the
+    /// new integration can't run backwards.
+    pub fn set_integration(&mut self, integration: Integration) {
+        self.integration = integration;
+        self.reversed &= integration.is_time_reversible();
+    }
+
+    /// Runs time backwards from the next step on. Only time-reversible
+    /// integrations can, so returns whether time now runs backwards.
+    pub fn set_reversed(&mut self, reversed: bool) -> bool {
+        self.reversed = reversed && self.integration.is_time_reversible();
+        self.reversed
+    }
+
+    pub fn is_reversed(&self) -> bool {
+        self.reversed
+    }
+
+    // The step length, negative while running backwards
+    fn signed_dt(&self) -> f32 {
+        match self.is_reversed() {
+            true => -self.integration_parameters.dt,
+            false => self.integration_parameters.dt,
+        }
     }
 
     pub fn step(&mut self) {
-        for (body, acceleration) in self.bodies.result() {
-            body.apply_acceleration_to_rigidbody(
-                &mut self.rigid_body_set,
-                acceleration,
-            )
+        // Keep the current state around for render interpolation
+        for body in self.bodies.iter_mut() {
+            body.save_previous();
+        }
+
+        match self.integration {
+            Integration::Rapier => self.step_rapier(),
+            Integration::Collisionless(kind) => {
+                self.step_collisionless(kind.integrator())
+            }
+        }
+
+        self.enforce_boundary();
+        self.time += self.signed_dt();
+    }
+
+    fn step_rapier(&mut self) {
+        match (self.gravity_solver, self.softening) {
+            (GravitySolver::BruteForce, Softening::None) => {
+                for (body, acceleration) in self.bodies.result() {
+                    body.apply_acceleration_to_rigidbody(
+                        &mut self.rigid_body_set,
+                        acceleration,
+                    )
+                }
+            }
+            (solver, softening) => {
+                let points = self
+                    .bodies
+                    .iter()
+                    .map(|body| (body.position(), body.mu()))
+                    .collect::<Vec<_>>();
+                let accelerations = gravity(&points, solver, softening);
+                for (body, acceleration) in
+                    self.bodies.iter_mut().zip(accelerations)
+                {
+                    body.apply_acceleration_to_rigidbody(
+                        &mut self.rigid_body_set,
+                        acceleration,
+                    )
+                }
+            }
         }
 
         self.physics_pipeline.step(
@@ -64,15 +273,367 @@ impl PhysicsContext {
             &mut self.narrow_phase,
             &mut self.rigid_body_set,
             &mut self.collider_set,
-            &mut ImpulseJointSet::new(),
-            &mut MultibodyJointSet::new(),
+            &mut self.impulse_joint_set,
+            &mut self.multibody_joint_set,
             &mut self.ccd_solver,
             &(),
             &(),
         );
 
+        // Pairs of bodies in contact, ignoring the wall
+        let parent = |handle| self.collider_set.get(handle)?.parent();
+        let contacts = self
+            .narrow_phase
+            .contact_pairs()
+            .filter(|pair| pair.has_any_active_contact)
+            .filter_map(|pair| {
+                Some((parent(pair.collider1)?, parent(pair.collider2)?))
+            })
+            .collect::<Vec<_>>();
+
+        // Those that weren't touching the step before just collided
+        let mut counts = HashMap::<RigidBodyHandle, u32>::new();
+        for &(a, b) in &contacts {
+            if !self.touching.contains(&(a, b)) {
+                *counts.entry(a).or_default() += 1;
+                *counts.entry(b).or_default() += 1;
+            }
+        }
+        if !counts.is_empty() {
+            for body in self.bodies.iter_mut() {
+                if let Some(&count) = counts.get(&body.rigidbody_handle) {
+                    body.add_collisions(count);
+                }
+            }
+        }
+        self.touching = contacts.iter().copied().collect();
+
+        if let CollisionMode::Merge { density } = self.collision_mode {
+            self.merge_bodies(contacts, density);
+        }
+    }
+
+    // Moves bodies under gravity alone, leaving rapier's pipeline idle
+    fn step_collisionless(&mut self, integrator: &dyn Integrator) {
+        let dt = self.signed_dt();
+        let (mut positions, mut velocities): (Vec<Vec2>, Vec<Vec2>) = self
+            .bodies
+            .iter()
+            .map(|body| {
+                let rb = &self.rigid_body_set[body.rigidbody_handle];
+                (Vec2::from(*rb.translation()), Vec2::from(*rb.linvel()))
+            })
+            .unzip();
+        let mus = self.bodies.iter().map(|body| body.mu()).collect::<Vec<_>>();
+
+        let (solver, softening) = (self.gravity_solver, self.softening);
+        // The last evaluation is kept to show on the bodies
+        let mut last_accelerations = vec![Vec2::ZERO; mus.len()];
+        integrator.step(
+            &mut positions,
+            &mut velocities,
+            dt,
+            &mut |positions: &[Vec2]| {
+                let points = positions
+                    .iter()
+                    .copied()
+                    .zip(mus.iter().copied())
+                    .collect::<Vec<_>>();
+                last_accelerations = gravity(&points, solver, softening);
+                last_accelerations.clone()
+            },
+        );
+
+        for (((body, position), velocity), acceleration) in self
+            .bodies
+            .iter_mut()
+            .zip(positions)
+            .zip(velocities)
+            .zip(last_accelerations)
+        {
+            body.set_acceleration(acceleration);
+            let rb = &mut self.rigid_body_set[body.rigidbody_handle];
+            let rotation = rb.rotation().angle() + rb.angvel() * dt;
+            rb.set_translation(position.into(), true);
+            rb.set_linvel(velocity.into(), true);
+            rb.set_rotation(Rotation::new(rotation), true);
+        }
+    }
+
+    /// Removes every body matching `predicate` from both the rapier sets and
+    /// the particle set. Returns how many were removed.
+    pub fn remove_bodies(
+        &mut self,
+        predicate: impl Fn(&Body) -> bool,
+    ) -> usize {
+        let mut kept = ParticleSet::new();
+        let mut removed = 0;
+        for body in self.bodies.iter() {
+            if predicate(body) {
+                self.rigid_body_set.remove(
+                    body.rigidbody_handle,
+                    &mut self.island_manager,
+                    &mut self.collider_set,
+                    &mut self.impulse_joint_set,
+                    &mut self.multibody_joint_set,
+                    true,
+                );
+                removed += 1;
+            } else {
+                kept.add(body.clone());
+            }
+        }
+        // The particle set can't remove, so it is rebuilt when it must
+        if removed > 0 {
+            self.bodies = kept;
+        }
+        removed
+    }
+
+    fn merge_bodies(
+        &mut self,
+        contacts: Vec<(RigidBodyHandle, RigidBodyHandle)>,
+        density: DensityModel,
+    ) {
+        let groups = merge_groups(contacts);
+        if groups.is_empty() {
+            return;
+        }
+
+        let merged = groups
+            .iter()
+            .map(|group| {
+                let parts = group
+                    .iter()
+                    .map(|&handle| {
+                        let rb = &self.rigid_body_set[handle];
+                        let coll = &self.collider_set[rb.colliders()[0]];
+                        BodyDescriptor::from_rigid_body(rb, coll)
+                    })
+                    .collect::<Vec<_>>();
+                merge(&parts, density)
+            })
+            .collect::<Vec<_>>();
+
+        let removed = groups.into_iter().flatten().collect::<Vec<_>>();
+        self.remove_bodies(|body| removed.contains(&body.rigidbody_handle));
+        for body in merged {
+            self.spawn(&body);
+        }
+    }
+
+    fn enforce_boundary(&mut self) {
+        let world_radius = self.world_radius;
+        let outside =
+            |position: Vec2| position.length_squared() > world_radius.powi(2);
+
+        match self.boundary {
+            BoundaryPolicy::Wrap => {
+                for body in self.bodies.iter_mut() {
+                    let rb = &mut self.rigid_body_set[body.rigidbody_handle];
+                    let position: Vec2 = (*rb.translation()).into();
+                    if outside(position) {
+                        let wrapped = wrap_position(position, world_radius);
+                        rb.set_translation(wrapped.into(), true);
+                        body.sync_to_rigidbody(
+                            &self.rigid_body_set,
+                            &self.collider_set,
+                        );
+                        // Don't interpolate across the world
+                        body.save_previous();
+                    }
+                }
+            }
+            BoundaryPolicy::Despawn => {
+                let rigid_body_set = &self.rigid_body_set;
+                let escaped = self
+                    .bodies
+                    .iter()
+                    .filter(|body| {
+                        outside(
+                            (*rigid_body_set[body.rigidbody_handle]
+                                .translation())
+                            .into(),
+                        )
+                    })
+                    .map(|body| body.rigidbody_handle)
+                    .collect::<Vec<_>>();
+                if !escaped.is_empty() {
+                    self.remove_bodies(|body| {
+                        escaped.contains(&body.rigidbody_handle)
+                    });
+                }
+            }
+            // Rapier's wall collider bounces bodies, unless it isn't stepped
+            BoundaryPolicy::Wall { restitution }
+                if self.integration != Integration::Rapier =>
+            {
+                for body in self.bodies.iter() {
+                    let rb = &mut self.rigid_body_set[body.rigidbody_handle];
+                    let position: Vec2 = (*rb.translation()).into();
+                    let limit = (world_radius - body.radius()).max(0.0);
+                    if position.length_squared() <= limit.powi(2) {
+                        continue;
+                    }
+
+                    let normal = position.normalize_or_zero();
+                    let mut velocity: Vec2 = (*rb.linvel()).into();
+                    let outward = velocity.dot(normal);
+                    if outward > 0.0 {
+                        velocity -= (1.0 + restitution) * outward * normal;
+                    }
+                    rb.set_translation((normal * limit).into(), true);
+                    rb.set_linvel(velocity.into(), true);
+                }
+            }
+            BoundaryPolicy::Wall { .. } | BoundaryPolicy::Open => (),
+        }
+
         for body in self.bodies.iter_mut() {
             body.sync_to_rigidbody(&self.rigid_body_set, &self.collider_set);
         }
     }
 }
+
+// Accelerations of `(position, mu)` points from the chosen solver
+fn gravity(
+    points: &[(Vec2, f32)],
+    solver: GravitySolver,
+    softening: Softening,
+) -> Vec<Vec2> {
+    match solver {
+        GravitySolver::BruteForce => accelerations(points, softening),
+        GravitySolver::BarnesHut { theta } => {
+            let tree = QuadTree::new(points.iter().copied());
+            points
+                .iter()
+                .map(|(position, _)| {
+                    tree.acceleration(*position, theta, softening)
+                })
+                .collect()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use glam::Vec2;
+    use rand::{Rng, SeedableRng};
+    use rand_pcg::Pcg32;
+    use rapier2d::prelude::*;
+
+    use super::PhysicsContext;
+    use crate::sim::{BodyDescriptor, BodyId};
+
+    fn descriptor(x: f32) -> BodyDescriptor {
+        BodyDescriptor {
+            mass: Some(1.0),
+            ..BodyDescriptor::new(Vec2::new(x, 0.0), 0.5)
+        }
+    }
+
+    // Every body has its own rigid body and collider, and rapier holds
+    // nothing else but the wall
+    fn assert_consistent(physics_context: &PhysicsContext) {
+        let count = physics_context.body_count();
+        assert_eq!(physics_context.rigid_body_set.len(), count);
+        let wall = physics_context.wall_handle.is_some() as usize;
+        assert_eq!(physics_context.collider_set.len(), count + wall);
+
+        let mut ids = HashSet::new();
+        for body in physics_context.bodies() {
+            assert!(ids.insert(body.id()), "{:?} twice", body.id());
+            let rb = &physics_context.rigid_body_set[body.rigidbody_handle];
+            assert_eq!(rb.colliders(), &[body.collider_handle]);
+            assert_eq!(
+                physics_context.collider_set[body.collider_handle].parent(),
+                Some(body.rigidbody_handle)
+            );
+        }
+    }
+
+    #[test]
+    fn interleaved_spawns_and_despawns_stay_consistent() {
+        let mut physics_context = PhysicsContext::new();
+        let mut rng = Pcg32::seed_from_u64(3);
+        let mut alive = Vec::<BodyId>::new();
+        let mut seen = HashSet::new();
+
+        for i in 0..300 {
+            if alive.is_empty() || rng.gen_bool(0.6) {
+                let id = physics_context
+                    .spawn(&descriptor(rng.gen_range(-50.0..50.0)));
+                assert!(seen.insert(id), "{:?} was reused", id);
+                alive.push(id);
+            } else {
+                let id = alive.swap_remove(rng.gen_range(0..alive.len()));
+                assert!(physics_context.despawn(id));
+                assert!(!physics_context.despawn(id));
+                assert!(physics_context.body(id).is_none());
+            }
+            if i % 10 == 0 {
+                physics_context.step();
+            }
+            assert_consistent(&physics_context);
+        }
+
+        let mut ids = physics_context
+            .bodies()
+            .map(|body| body.id())
+            .collect::<Vec<_>>();
+        ids.sort();
+        alive.sort();
+        assert_eq!(ids, alive);
+    }
+
+    #[test]
+    fn lookup_finds_the_spawned_body() {
+        let mut physics_context = PhysicsContext::new();
+        let ids = (0..5)
+            .map(|i| physics_context.spawn(&descriptor(i as f32 * 3.0)))
+            .collect::<Vec<_>>();
+        physics_context.despawn(ids[1]);
+        for (i, &id) in ids.iter().enumerate().filter(|&(i, _)| i != 1) {
+            let body = physics_context.body(id).unwrap();
+            assert_eq!(body.position(), Vec2::new(i as f32 * 3.0, 0.0));
+        }
+    }
+
+    #[test]
+    fn despawning_removes_attached_joints() {
+        let mut physics_context = PhysicsContext::new();
+        let a = physics_context.spawn(&descriptor(-1.0));
+        let b = physics_context.spawn(&descriptor(1.0));
+        let handle = |id| physics_context.body(id).unwrap().rigidbody_handle;
+        let (handle_a, handle_b) = (handle(a), handle(b));
+        let joint = RevoluteJointBuilder::new()
+            .local_anchor1(point![1.0, 0.0])
+            .local_anchor2(point![-1.0, 0.0]);
+        physics_context
+            .impulse_joint_set
+            .insert(handle_a, handle_b, joint, true);
+        physics_context.step();
+
+        physics_context.despawn(a);
+        assert_eq!(physics_context.impulse_joint_set.len(), 0);
+        assert_consistent(&physics_context);
+        physics_context.step();
+    }
+
+    #[test]
+    fn bodies_remember_their_acceleration() {
+        let mut physics_context = PhysicsContext::new();
+        let a = physics_context.spawn(&descriptor(-5.0));
+        let b = physics_context.spawn(&descriptor(5.0));
+        assert_eq!(physics_context.body(a).unwrap().acceleration(), Vec2::ZERO);
+
+        physics_context.step();
+        // Each is pulled toward the other
+        let toward = |id| physics_context.body(id).unwrap().acceleration();
+        assert!(toward(a).x > 0.0 && toward(a).y.abs() < 1e-6);
+        assert!(toward(b).x < 0.0);
+        assert!((toward(a) + toward(b)).length() < 1e-5);
+    }
+}
@@ -0,0 +1,551 @@
+use std::f32::consts::PI;
+use std::fmt;
+use std::str::FromStr;
+
+use glam::Vec2;
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg32;
+
+use crate::sim::softening::accelerations;
+use crate::sim::{
+    BodyDescriptor, BoundaryPolicy, Globals, Integration, IntegratorKind,
+    Scenario, Softening,
+};
+
+// Presets work in units where G = 1
+const PRESET_GRAVITY: f32 = 1.0;
+// Size and mass of each body of the figure-eight
+const FIGURE_EIGHT_LENGTH: f32 = 20.0;
+const FIGURE_EIGHT_MASS: f32 = 100.0;
+
+/// A built-in initial condition.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Preset {
+    CircularBinary,
+    EllipticBinary,
+    SolarSystem,
+    FigureEight,
+    PlummerSphere,
+    ExponentialDisc,
+    CollidingDiscs,
+}
+
+impl Preset {
+    pub const ALL: [Preset; 7] = [
+        Preset::CircularBinary,
+        Preset::EllipticBinary,
+        Preset::SolarSystem,
+        Preset::FigureEight,
+        Preset::PlummerSphere,
+        Preset::ExponentialDisc,
+        Preset::CollidingDiscs,
+    ];
+
+    /// The name used to pick the preset, e.g. `?preset=figure-eight`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Preset::CircularBinary => "circular-binary",
+            Preset::EllipticBinary => "elliptic-binary",
+            Preset::SolarSystem => "solar-system",
+            Preset::FigureEight => "figure-eight",
+            Preset::PlummerSphere => "plummer-sphere",
+            Preset::ExponentialDisc => "exponential-disc",
+            Preset::CollidingDiscs => "colliding-discs",
+        }
+    }
+
+    /// Builds the preset. Presets with a fixed layout ignore `num_bodies`
+    /// and `seed`.
+    pub fn scenario(&self, num_bodies: usize, seed: u64) -> Scenario {
+        match self {
+            Preset::CircularBinary => binary(0.0),
+            Preset::EllipticBinary => binary(0.6),
+            Preset::SolarSystem => solar_system(),
+            Preset::FigureEight => figure_eight(),
+            Preset::PlummerSphere => plummer_sphere(num_bodies, seed),
+            Preset::ExponentialDisc => exponential_disc(num_bodies, seed),
+            Preset::CollidingDiscs => colliding_discs(num_bodies, seed),
+        }
+    }
+}
+
+impl fmt::Display for Preset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Preset {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Preset::ALL
+            .into_iter()
+            .find(|preset| preset.name() == name)
+            .ok_or_else(|| {
+                let names = Preset::ALL.map(|preset| preset.name());
+                format!(
+                    "unknown preset {} (expected one of {})",
+                    name,
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+fn globals(integrator: IntegratorKind) -> Globals {
+    Globals {
+        universal_gravity: PRESET_GRAVITY,
+        gravity_amplifier: 1.0,
+        integration: Integration::Collisionless(integrator),
+        ..Default::default()
+    }
+}
+
+fn body(
+    position: Vec2,
+    velocity: Vec2,
+    mass: f32,
+    radius: f32,
+) -> BodyDescriptor {
+    BodyDescriptor {
+        velocity,
+        mass: Some(mass),
+        ..BodyDescriptor::new(position, radius)
+    }
+}
+
+// Shifts bodies so their center of mass sits still at the origin
+fn center(bodies: &mut [BodyDescriptor]) {
+    let mass = bodies.iter().map(|body| body.mass.unwrap()).sum::<f32>();
+    let (position, velocity) = bodies.iter().fold(
+        (Vec2::ZERO, Vec2::ZERO),
+        |(position, velocity), body| {
+            let body_mass = body.mass.unwrap();
+            (
+                position + body.position * body_mass,
+                velocity + body.velocity * body_mass,
+            )
+        },
+    );
+    for body in bodies {
+        body.position -= position / mass;
+        body.velocity -= velocity / mass;
+    }
+}
+
+fn points(bodies: &[BodyDescriptor]) -> Vec<(Vec2, f32)> {
+    bodies
+        .iter()
+        .map(|body| (body.position, PRESET_GRAVITY * body.mass.unwrap()))
+        .collect()
+}
+
+/// Two stars on Keplerian orbits of semi-major axis 30 with the given
+/// eccentricity, starting at apocenter.
+pub fn binary(eccentricity: f32) -> Scenario {
+    let (semi_major_axis, masses) = (30.0, [600.0, 300.0]);
+    let mu = PRESET_GRAVITY * (masses[0] + masses[1]);
+    let apocenter = semi_major_axis * (1.0 + eccentricity);
+    // Vis-viva at apocenter
+    let speed = (mu / semi_major_axis * (1.0 - eccentricity)
+        / (1.0 + eccentricity))
+        .sqrt();
+
+    let mut bodies = vec![
+        body(Vec2::ZERO, Vec2::ZERO, masses[0], 3.0),
+        body(
+            Vec2::new(apocenter, 0.0),
+            Vec2::new(0.0, speed),
+            masses[1],
+            2.0,
+        ),
+    ];
+    center(&mut bodies);
+    Scenario::new(globals(IntegratorKind::Yoshida4), bodies)
+}
+
+/// The sun and its eight planets on circular orbits. Mass ratios are real,
+/// but distances are compressed to the square root of the real ones so the
+/// whole system fits in the world.
+pub fn solar_system() -> Scenario {
+    let sun_mass = 1000.0;
+    // Semi-major axis in AU, mass relative to the sun and drawn radius
+    let planets = [
+        (0.387, 1.66e-7, 0.4),
+        (0.723, 2.45e-6, 0.6),
+        (1.0, 3.00e-6, 0.6),
+        (1.524, 3.23e-7, 0.5),
+        (5.203, 9.55e-4, 1.4),
+        (9.537, 2.86e-4, 1.2),
+        (19.19, 4.37e-5, 0.9),
+        (30.07, 5.15e-5, 0.9),
+    ];
+
+    let mut bodies = vec![body(Vec2::ZERO, Vec2::ZERO, sun_mass, 3.0)];
+    for (i, (au, mass_ratio, radius)) in planets.into_iter().enumerate() {
+        let distance = 10.0 * f32::sqrt(au);
+        let speed = (PRESET_GRAVITY * sun_mass / distance).sqrt();
+        // Spread the planets around the sun by the golden angle
+        let direction = Vec2::from_angle(i as f32 * 2.39996);
+        bodies.push(body(
+            direction * distance,
+            direction.perp() * speed,
+            sun_mass * mass_ratio,
+            radius,
+        ));
+    }
+    center(&mut bodies);
+    Scenario::new(globals(IntegratorKind::Yoshida4), bodies)
+}
+
+/// Three equal masses chasing each other around the Chenciner-Montgomery
+/// figure-eight orbit.
+pub fn figure_eight() -> Scenario {
+    // Simó's initial conditions for G = m = 1, scaled up to world size
+    let (length, mass) = (FIGURE_EIGHT_LENGTH, FIGURE_EIGHT_MASS);
+    let velocity_scale = (PRESET_GRAVITY * mass / length).sqrt();
+    let position = Vec2::new(0.970_004_4, -0.243_087_53) * length;
+    let velocity = Vec2::new(-0.932_407_4, -0.864_731_5) * velocity_scale;
+
+    let bodies = vec![
+        body(position, -velocity / 2.0, mass, 1.5),
+        body(-position, -velocity / 2.0, mass, 1.5),
+        body(Vec2::ZERO, velocity, mass, 1.5),
+    ];
+    Scenario::new(globals(IntegratorKind::Yoshida4), bodies)
+}
+
+/// The period of [`figure_eight`], in simulated seconds.
+pub fn figure_eight_period() -> f32 {
+    6.325_913
+        * (FIGURE_EIGHT_LENGTH.powi(3) / (PRESET_GRAVITY * FIGURE_EIGHT_MASS))
+            .sqrt()
+}
+
+/// A star cluster following the projected Plummer profile, with isotropic
+/// velocities scaled into virial equilibrium.
+pub fn plummer_sphere(num_bodies: usize, seed: u64) -> Scenario {
+    let (total_mass, scale_radius) = (1000.0, 10.0);
+    let softening = Softening::Plummer { length: 1.0 };
+    let mut rng = Pcg32::seed_from_u64(seed);
+
+    let mass = total_mass / num_bodies as f32;
+    let mut bodies = (0..num_bodies)
+        .map(|_| {
+            // Invert the projected mass profile R² / (R² + a²), cutting off
+            // the outermost percent
+            let fraction = rng.gen_range(0.0..0.99f32);
+            let radius = scale_radius * (fraction / (1.0 - fraction)).sqrt();
+            let position = Vec2::from_angle(rng.gen_range(0.0..2.0 * PI));
+
+            // Aarseth, Hénon & Wielen's rejection sampling of the speed, as
+            // a fraction of the local escape speed
+            let q = loop {
+                let (q, g) = (rng.gen::<f32>(), rng.gen_range(0.0..0.1f32));
+                if g < q * q * (1.0 - q * q).powf(3.5) {
+                    break q;
+                }
+            };
+            let escape = (2.0 * PRESET_GRAVITY * total_mass
+                / (radius * radius + scale_radius * scale_radius).sqrt())
+            .sqrt();
+            let velocity = Vec2::from_angle(rng.gen_range(0.0..2.0 * PI));
+
+            body(position * radius, velocity * q * escape, mass, 0.3)
+        })
+        .collect::<Vec<_>>();
+    center(&mut bodies);
+
+    // A flattened cluster isn't in equilibrium with 3D speeds, so rescale
+    // them until the virial ratio 2K / |W| is one
+    let ratio = virial_ratio(&bodies, softening);
+    for body in &mut bodies {
+        body.velocity /= ratio.sqrt();
+    }
+
+    Scenario::new(
+        Globals {
+            softening,
+            boundary: BoundaryPolicy::Open,
+            ..globals(IntegratorKind::Leapfrog)
+        },
+        bodies,
+    )
+}
+
+/// Twice the kinetic energy over the magnitude of the potential energy.
+pub fn virial_ratio(bodies: &[BodyDescriptor], softening: Softening) -> f32 {
+    let kinetic = bodies
+        .iter()
+        .map(|body| 0.5 * body.mass.unwrap() * body.velocity.length_squared())
+        .sum::<f32>();
+    let mut potential = 0.0;
+    for (i, body1) in bodies.iter().enumerate() {
+        for body2 in &bodies[i + 1..] {
+            let distance = body1.position.distance(body2.position);
+            potential += body2.mass.unwrap()
+                * softening
+                    .potential(distance, PRESET_GRAVITY * body1.mass.unwrap());
+        }
+    }
+    2.0 * kinetic / potential.abs()
+}
+
+// A cold exponential disc around a central bulge, rotating counterclockwise
+// with the circular velocity of the bodies' own gravity
+fn disc(
+    rng: &mut Pcg32,
+    num_bodies: usize,
+    disc_mass: f32,
+    scale_length: f32,
+    softening: Softening,
+) -> Vec<BodyDescriptor> {
+    let bulge_mass = 0.25 * disc_mass;
+    let mass = disc_mass / num_bodies as f32;
+
+    let mut bodies = vec![body(Vec2::ZERO, Vec2::ZERO, bulge_mass, 2.0)];
+    for _ in 0..num_bodies {
+        // R e^(-R/h) is a gamma distribution, the sum of two exponentials.
+        // Cut it off at five scale lengths.
+        let radius = loop {
+            let radius = -scale_length
+                * (rng.gen_range(f32::EPSILON..1.0)
+                    * rng.gen_range(f32::EPSILON..1.0))
+                .ln();
+            if radius < 5.0 * scale_length {
+                break radius;
+            }
+        };
+        let position = Vec2::from_angle(rng.gen_range(0.0..2.0 * PI));
+        bodies.push(body(position * radius, Vec2::ZERO, mass, 0.3));
+    }
+
+    // Pull towards the center felt by each body, averaged over its
+    // neighbours in radius to smooth out the graininess of the disc
+    let pulls = accelerations(&points(&bodies), softening)
+        .into_iter()
+        .zip(&bodies)
+        .map(|(acceleration, body)| {
+            -acceleration.dot(body.position.normalize_or_zero())
+        })
+        .collect::<Vec<_>>();
+    let mut order = (1..bodies.len()).collect::<Vec<_>>();
+    order.sort_by(|&a, &b| {
+        let radius = |i: usize| bodies[i].position.length();
+        radius(a).total_cmp(&radius(b))
+    });
+    const NEIGHBOURS: usize = 8;
+    for (rank, &i) in order.iter().enumerate() {
+        let window = &order[rank.saturating_sub(NEIGHBOURS)
+            ..(rank + NEIGHBOURS + 1).min(order.len())];
+        let pull =
+            window.iter().map(|&j| pulls[j]).sum::<f32>() / window.len() as f32;
+        let position = bodies[i].position;
+        let speed = (position.length() * pull.max(0.0)).sqrt();
+        bodies[i].velocity = position.normalize_or_zero().perp() * speed;
+    }
+    bodies
+}
+
+/// A rotating galaxy: an exponential disc with circular velocities, around a
+/// central bulge. Having no random motions, it soon grows spiral arms and
+/// clumps.
+pub fn exponential_disc(num_bodies: usize, seed: u64) -> Scenario {
+    let softening = Softening::Plummer { length: 2.0 };
+    let mut rng = Pcg32::seed_from_u64(seed);
+    let mut bodies = disc(&mut rng, num_bodies, 1000.0, 8.0, softening);
+    center(&mut bodies);
+
+    Scenario::new(
+        Globals {
+            softening,
+            boundary: BoundaryPolicy::Open,
+            ..globals(IntegratorKind::Leapfrog)
+        },
+        bodies,
+    )
+}
+
+/// Two exponential discs falling into each other on a bound, off-center
+/// orbit.
+pub fn colliding_discs(num_bodies: usize, seed: u64) -> Scenario {
+    let softening = Softening::Plummer { length: 2.0 };
+    let mut rng = Pcg32::seed_from_u64(seed);
+    let disc_mass = 500.0;
+    let offset = Vec2::new(40.0, 12.0);
+    // Most of the escape speed of the two discs and their bulges
+    let total_mass = 2.0 * 1.25 * disc_mass;
+    let speed = 0.7
+        * (2.0 * PRESET_GRAVITY * total_mass / (2.0 * offset.length())).sqrt();
+
+    let mut bodies = Vec::with_capacity(num_bodies + 2);
+    let halves = [num_bodies / 2, num_bodies - num_bodies / 2];
+    for (side, half) in [-1.0, 1.0].into_iter().zip(halves) {
+        let mut galaxy = disc(&mut rng, half, disc_mass, 5.0, softening);
+        for body in &mut galaxy {
+            body.position += offset * side;
+            body.velocity += Vec2::new(-speed * side / 2.0, 0.0);
+        }
+        bodies.extend(galaxy);
+    }
+    center(&mut bodies);
+
+    Scenario::new(
+        Globals {
+            world_radius: 120.0,
+            softening,
+            boundary: BoundaryPolicy::Open,
+            ..globals(IntegratorKind::Leapfrog)
+        },
+        bodies,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec2;
+
+    use super::{
+        binary, colliding_discs, exponential_disc, figure_eight,
+        figure_eight_period, plummer_sphere, solar_system, virial_ratio,
+        Preset, PRESET_GRAVITY,
+    };
+    use crate::sim::{BodyDescriptor, Scenario};
+
+    fn radii(scenario: &Scenario) -> Vec<f32> {
+        scenario
+            .bodies
+            .iter()
+            .map(|body| body.position.length())
+            .collect()
+    }
+
+    // Runs a scenario for `steps` and describes where its bodies end up
+    fn run(scenario: &Scenario, steps: usize) -> Scenario {
+        let mut physics_context = scenario.build().unwrap();
+        for _ in 0..steps {
+            physics_context.step();
+        }
+        let bodies = physics_context
+            .bodies()
+            .map(|body| {
+                BodyDescriptor::from_rigid_body(
+                    &physics_context.rigid_body_set[body.rigidbody_handle],
+                    &physics_context.collider_set[body.collider_handle],
+                )
+            })
+            .collect();
+        Scenario::new(scenario.globals.clone(), bodies)
+    }
+
+    #[test]
+    fn presets_are_valid_and_named() {
+        for preset in Preset::ALL {
+            let scenario = preset.scenario(200, 7);
+            assert_eq!(scenario.validate(), Ok(()), "{}", preset);
+            assert_eq!(scenario, preset.scenario(200, 7), "{}", preset);
+            assert_eq!(preset.name().parse(), Ok(preset));
+        }
+        assert!("nothing".parse::<Preset>().is_err());
+    }
+
+    #[test]
+    fn binary_has_the_expected_energy() {
+        for eccentricity in [0.0, 0.6] {
+            let bodies = binary(eccentricity).bodies;
+            let (m1, m2) = (bodies[0].mass.unwrap(), bodies[1].mass.unwrap());
+            let momentum = bodies[0].velocity * m1 + bodies[1].velocity * m2;
+            assert!(momentum.length() < 1e-3);
+
+            let kinetic = 0.5 * m1 * bodies[0].velocity.length_squared()
+                + 0.5 * m2 * bodies[1].velocity.length_squared();
+            let potential = -PRESET_GRAVITY * m1 * m2
+                / bodies[0].position.distance(bodies[1].position);
+            let expected = -PRESET_GRAVITY * m1 * m2 / (2.0 * 30.0);
+            let energy = kinetic + potential;
+            assert!((energy - expected).abs() < 1e-3 * expected.abs());
+        }
+    }
+
+    #[test]
+    fn solar_system_orbits_stay_circular() {
+        let scenario = solar_system();
+        let before = radii(&scenario);
+        let after = radii(&run(&scenario, 600));
+        for (before, after) in before.iter().zip(&after).skip(1) {
+            assert!((after - before).abs() < 0.02 * before, "{}", before);
+        }
+    }
+
+    #[test]
+    fn figure_eight_returns_after_one_period() {
+        let scenario = figure_eight();
+        let steps = (figure_eight_period() / scenario.globals.timestep).round();
+        let after = run(&scenario, steps as usize);
+        for (before, after) in scenario.bodies.iter().zip(&after.bodies) {
+            let error = before.position.distance(after.position);
+            assert!(error < 0.5, "off by {}", error);
+        }
+    }
+
+    #[test]
+    fn plummer_sphere_is_virialized() {
+        let scenario = plummer_sphere(1000, 3);
+        let ratio = virial_ratio(&scenario.bodies, scenario.globals.softening);
+        assert!((ratio - 1.0).abs() < 1e-3, "{}", ratio);
+
+        // Half the mass lies within the scale radius
+        let mut radii = radii(&scenario);
+        radii.sort_by(f32::total_cmp);
+        let half_mass_radius = radii[radii.len() / 2];
+        assert!(
+            (half_mass_radius - 10.0).abs() < 1.0,
+            "{}",
+            half_mass_radius
+        );
+    }
+
+    #[test]
+    fn exponential_disc_rotates_in_place() {
+        let scenario = exponential_disc(300, 5);
+        let stars = &scenario.bodies[1..];
+
+        // The mean radius of an exponential disc is twice its scale length,
+        // or 1.82 times once it is cut off at five
+        let mean = stars.iter().map(|body| body.position.length()).sum::<f32>()
+            / stars.len() as f32;
+        assert!((mean - 1.82 * 8.0).abs() < 1.5, "{}", mean);
+        assert!(stars
+            .iter()
+            .all(|body| body.position.perp_dot(body.velocity) > 0.0));
+
+        // With circular velocities the disc keeps its size for a while, where
+        // a still one would collapse
+        let half_mass_radius = |scenario: &Scenario| {
+            let mut radii = radii(scenario);
+            radii.sort_by(f32::total_cmp);
+            radii[radii.len() / 2]
+        };
+        let before = half_mass_radius(&scenario);
+        let after = half_mass_radius(&run(&scenario, 120));
+        assert!((after - before).abs() < 0.05 * before, "{}", after);
+    }
+
+    #[test]
+    fn colliding_discs_approach_each_other() {
+        let scenario = colliding_discs(200, 9);
+        assert_eq!(scenario.bodies.len(), 202);
+        let (left, right) = scenario.bodies.split_at(101);
+        let center = |bodies: &[BodyDescriptor]| {
+            bodies
+                .iter()
+                .fold((Vec2::ZERO, Vec2::ZERO), |(p, v), body| {
+                    (p + body.position, v + body.velocity)
+                })
+        };
+        let (left, right) = (center(left), center(right));
+        // Moving towards each other
+        assert!((right.0 - left.0).dot(right.1 - left.1) < 0.0);
+    }
+}
@@ -0,0 +1,462 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use glam::Vec2;
+use rapier2d::prelude::*;
+use ron::extensions::Extensions;
+use serde::{Deserialize, Serialize};
+
+use crate::sim::physics::PhysicsContext;
+use crate::sim::{
+    BodyId, BoundaryPolicy, CollisionMode, DensityModel, Integration,
+    Softening, BODY_FRICTION, BODY_RESTITUTION, GRAVITY_AMPLIFIER,
+    UNIVERSAL_GRAVITY, WORLD_RADIUS,
+};
+
+/// The scenario format version this build reads and writes.
+pub const SCENARIO_VERSION: u32 = 1;
+
+/// Initial conditions for a simulation, loadable from RON or JSON.
+///
+/// ```ron
+/// (
+///     version: 1,
+///     globals: (world_radius: 50.0, boundary: Wrap),
+///     bodies: [
+///         (position: (0.0, 0.0), radius: 2.0, mass: 100.0),
+///         (position: (20.0, 0.0), velocity: (0.0, 3.0), radius: 0.5),
+///     ],
+/// )
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    pub version: u32,
+    #[serde(default)]
+    pub globals: Globals,
+    pub bodies: Vec<BodyDescriptor>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Globals {
+    pub universal_gravity: f32,
+    pub gravity_amplifier: f32,
+    pub world_radius: f32,
+    pub boundary: BoundaryPolicy,
+    pub collision_mode: CollisionMode,
+    pub softening: Softening,
+    pub integration: Integration,
+    pub timestep: f32,
+}
+
+impl Default for Globals {
+    fn default() -> Self {
+        Self {
+            universal_gravity: UNIVERSAL_GRAVITY,
+            gravity_amplifier: GRAVITY_AMPLIFIER,
+            world_radius: WORLD_RADIUS,
+            boundary: BoundaryPolicy::default(),
+            collision_mode: CollisionMode::default(),
+            softening: Softening::default(),
+            integration: Integration::default(),
+            timestep: IntegrationParameters::default().dt,
+        }
+    }
+}
+
+/// A single body. Its mass comes from either `density` or `mass`; with
+/// neither, the collider's default density is used. Without an `id`, the
+/// body gets the next free one when spawned.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BodyDescriptor {
+    #[serde(default)]
+    pub id: Option<BodyId>,
+    pub position: Vec2,
+    #[serde(default)]
+    pub velocity: Vec2,
+    #[serde(default)]
+    pub rotation: f32,
+    #[serde(default)]
+    pub angular_velocity: f32,
+    pub radius: f32,
+    #[serde(default)]
+    pub density: Option<f32>,
+    #[serde(default)]
+    pub mass: Option<f32>,
+    #[serde(default = "default_restitution")]
+    pub restitution: f32,
+    #[serde(default = "default_friction")]
+    pub friction: f32,
+}
+
+fn default_restitution() -> f32 {
+    BODY_RESTITUTION
+}
+
+fn default_friction() -> f32 {
+    BODY_FRICTION
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ScenarioError {
+    /// The text isn't valid RON or JSON for a scenario
+    Parse(String),
+    /// The scenario was written for a format we can't read
+    UnsupportedVersion(u32),
+    /// A field holds a bad value, e.g. `bodies[3].radius`
+    InvalidField { field: String, reason: &'static str },
+}
+
+impl fmt::Display for ScenarioError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScenarioError::Parse(message) => write!(f, "{}", message),
+            ScenarioError::UnsupportedVersion(version) => write!(
+                f,
+                "version: unsupported scenario version {} (expected {})",
+                version, SCENARIO_VERSION
+            ),
+            ScenarioError::InvalidField { field, reason } => {
+                write!(f, "{}: {}", field, reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ScenarioError {}
+
+impl Scenario {
+    pub fn new(globals: Globals, bodies: Vec<BodyDescriptor>) -> Self {
+        Self {
+            version: SCENARIO_VERSION,
+            globals,
+            bodies,
+        }
+    }
+
+    pub fn from_ron(source: &str) -> Result<Self, ScenarioError> {
+        let scenario: Self = ron_options()
+            .from_str(source)
+            .map_err(|e| ScenarioError::Parse(e.to_string()))?;
+        scenario.validate()?;
+        Ok(scenario)
+    }
+
+    pub fn from_json(source: &str) -> Result<Self, ScenarioError> {
+        let scenario: Self = serde_json::from_str(source)
+            .map_err(|e| ScenarioError::Parse(e.to_string()))?;
+        scenario.validate()?;
+        Ok(scenario)
+    }
+
+    pub fn to_ron(&self) -> String {
+        let config = ron::ser::PrettyConfig::default()
+            .extensions(Extensions::IMPLICIT_SOME);
+        ron_options()
+            .to_string_pretty(self, config)
+            .expect("Scenario is always serializable")
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self)
+            .expect("Scenario is always serializable")
+    }
+
+    pub fn validate(&self) -> Result<(), ScenarioError> {
+        if self.version != SCENARIO_VERSION {
+            return Err(ScenarioError::UnsupportedVersion(self.version));
+        }
+
+        let globals = &self.globals;
+        not_negative("globals.universal_gravity", globals.universal_gravity)?;
+        not_negative("globals.gravity_amplifier", globals.gravity_amplifier)?;
+        positive("globals.world_radius", globals.world_radius)?;
+        if let BoundaryPolicy::Wall { restitution } = globals.boundary {
+            not_negative("globals.boundary.restitution", restitution)?;
+        }
+        if let CollisionMode::Merge {
+            density: DensityModel::Constant { density },
+        } = globals.collision_mode
+        {
+            positive("globals.collision_mode.density", density)?;
+        }
+        match globals.softening {
+            Softening::None => (),
+            Softening::Plummer { length } | Softening::Spline { length } => {
+                positive("globals.softening.length", length)?
+            }
+        }
+        positive("globals.timestep", globals.timestep)?;
+
+        let mut ids = HashSet::new();
+        for (i, body) in self.bodies.iter().enumerate() {
+            let field = |name| format!("bodies[{}].{}", i, name);
+            if let Some(id) = body.id {
+                if !ids.insert(id) {
+                    return Err(ScenarioError::InvalidField {
+                        field: field("id"),
+                        reason: "must be unique",
+                    });
+                }
+            }
+            finite(&field("position"), body.position.is_finite())?;
+            finite(&field("velocity"), body.velocity.is_finite())?;
+            finite(&field("rotation"), body.rotation.is_finite())?;
+            finite(
+                &field("angular_velocity"),
+                body.angular_velocity.is_finite(),
+            )?;
+            positive(&field("radius"), body.radius)?;
+            match (body.density, body.mass) {
+                (Some(_), Some(_)) => {
+                    return Err(ScenarioError::InvalidField {
+                        field: field("mass"),
+                        reason: "give either a density or a mass, not both",
+                    })
+                }
+                (Some(density), None) => positive(&field("density"), density)?,
+                (None, Some(mass)) => positive(&field("mass"), mass)?,
+                (None, None) => (),
+            }
+            not_negative(&field("restitution"), body.restitution)?;
+            not_negative(&field("friction"), body.friction)?;
+        }
+        Ok(())
+    }
+
+    /// Validates the scenario and builds a physics context holding every
+    /// body.
+    pub fn build(&self) -> Result<PhysicsContext, ScenarioError> {
+        self.validate()?;
+        let mut physics_context = PhysicsContext::new();
+        physics_context.gravitational_constant =
+            self.globals.universal_gravity * self.globals.gravity_amplifier;
+        physics_context.world_radius = self.globals.world_radius;
+        physics_context.set_boundary(self.globals.boundary);
+        physics_context.collision_mode = self.globals.collision_mode;
+        physics_context.softening = self.globals.softening;
+        physics_context.set_integration(self.globals.integration);
+        physics_context.integration_parameters.dt = self.globals.timestep;
+
+        // Bodies without an id count on from the explicit ones, wherever
+        // they come in the list
+        if let Some(id) = self.bodies.iter().filter_map(|body| body.id).max() {
+            physics_context.reserve_body_ids(BodyId(id.0 + 1));
+        }
+        for body in &self.bodies {
+            physics_context.spawn(body);
+        }
+        Ok(physics_context)
+    }
+}
+
+impl BodyDescriptor {
+    pub fn new(position: Vec2, radius: f32) -> Self {
+        Self {
+            id: None,
+            position,
+            velocity: Vec2::ZERO,
+            rotation: 0.0,
+            angular_velocity: 0.0,
+            radius,
+            density: None,
+            mass: None,
+            restitution: BODY_RESTITUTION,
+            friction: BODY_FRICTION,
+        }
+    }
+
+    /// Describes the current state of a body, down to its exact mass. The
+    /// id is left for the caller to fill in.
+    pub fn from_rigid_body(
+        rigid_body: &RigidBody,
+        collider: &Collider,
+    ) -> Self {
+        Self {
+            id: None,
+            position: (*rigid_body.translation()).into(),
+            velocity: (*rigid_body.linvel()).into(),
+            rotation: rigid_body.rotation().angle(),
+            angular_velocity: rigid_body.angvel(),
+            radius: collider.shape().as_ball().unwrap().radius,
+            density: None,
+            mass: Some(rigid_body.mass()),
+            restitution: collider.restitution(),
+            friction: collider.friction(),
+        }
+    }
+
+    pub fn build(&self) -> (RigidBody, Collider) {
+        let rigid_body = RigidBodyBuilder::new(RigidBodyType::Dynamic)
+            .translation(self.position.into())
+            .linvel(self.velocity.into())
+            .angvel(self.angular_velocity)
+            .rotation(self.rotation)
+            .ccd_enabled(false) // Enable for higher accuracy
+            .build();
+
+        let mut collider = ColliderBuilder::ball(self.radius)
+            .restitution(self.restitution)
+            .friction(self.friction);
+        if let Some(density) = self.density {
+            collider = collider.density(density);
+        }
+        if let Some(mass) = self.mass {
+            collider = collider.mass(mass);
+        }
+        (rigid_body, collider.build())
+    }
+}
+
+// Optional fields can be written without `Some(...)`
+pub(crate) fn ron_options() -> ron::Options {
+    ron::Options::default().with_default_extension(Extensions::IMPLICIT_SOME)
+}
+
+fn not_negative(field: &str, value: f32) -> Result<(), ScenarioError> {
+    match value.is_finite() && value >= 0.0 {
+        true => Ok(()),
+        false => Err(ScenarioError::InvalidField {
+            field: field.to_owned(),
+            reason: "must be finite and not negative",
+        }),
+    }
+}
+
+fn positive(field: &str, value: f32) -> Result<(), ScenarioError> {
+    match value.is_finite() && value > 0.0 {
+        true => Ok(()),
+        false => Err(ScenarioError::InvalidField {
+            field: field.to_owned(),
+            reason: "must be positive",
+        }),
+    }
+}
+
+fn finite(field: &str, valid: bool) -> Result<(), ScenarioError> {
+    match valid {
+        true => Ok(()),
+        false => Err(ScenarioError::InvalidField {
+            field: field.to_owned(),
+            reason: "must be finite",
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec2;
+
+    use super::{Scenario, ScenarioError};
+
+    const SCENARIO: &str = r#"(
+        version: 1,
+        globals: (world_radius: 50.0),
+        bodies: [
+            (position: (0.0, 0.0), radius: 2.0, mass: 100.0),
+            (position: (20.0, 0.0), velocity: (0.0, 3.0), radius: 0.5),
+        ],
+    )"#;
+
+    #[test]
+    fn loads_ron() {
+        let scenario = Scenario::from_ron(SCENARIO).unwrap();
+        assert_eq!(scenario.globals.world_radius, 50.0);
+        assert_eq!(scenario.bodies[1].velocity, Vec2::new(0.0, 3.0));
+
+        let physics_context = scenario.build().unwrap();
+        assert_eq!(physics_context.world_radius, 50.0);
+        let masses = physics_context
+            .bodies()
+            .map(|body| body.mass())
+            .collect::<Vec<_>>();
+        assert_eq!(masses.len(), 2);
+        assert!((masses[0] - 100.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn round_trips_through_json() {
+        let scenario = Scenario::from_ron(SCENARIO).unwrap();
+        let json = scenario.to_json();
+        assert_eq!(Scenario::from_json(&json).unwrap(), scenario);
+    }
+
+    #[test]
+    fn points_at_bad_field() {
+        let source = SCENARIO.replace("radius: 0.5", "radius: -0.5");
+        let error = Scenario::from_ron(&source).unwrap_err();
+        assert_eq!(error.to_string(), "bodies[1].radius: must be positive");
+
+        let source = SCENARIO.replace("mass: 100.0", "mass: 1.0, density: 1.0");
+        let error = Scenario::from_ron(&source).unwrap_err();
+        assert!(error.to_string().starts_with("bodies[0].mass:"));
+    }
+
+    #[test]
+    fn keeps_body_ids() {
+        let source = SCENARIO
+            .replace("(position: (0.0, 0.0)", "(id: 7, position: (0.0, 0.0)");
+        let scenario = Scenario::from_ron(&source).unwrap();
+        let mut physics_context = scenario.build().unwrap();
+        let mut ids = physics_context
+            .bodies()
+            .map(|body| body.id().to_string())
+            .collect::<Vec<_>>();
+        ids.sort();
+        // Bodies without an id count on from the ones kept
+        assert_eq!(ids, ["7", "8"]);
+        let id = physics_context.spawn(&scenario.bodies[1]);
+        assert_eq!(id.to_string(), "9");
+
+        let source =
+            source.replace("(position: (20.0", "(id: 7, position: (20.0");
+        let error = Scenario::from_ron(&source).unwrap_err();
+        assert_eq!(error.to_string(), "bodies[1].id: must be unique");
+    }
+
+    #[test]
+    fn bodies_without_ids_skip_later_ones() {
+        let source =
+            SCENARIO.replace("(position: (20.0", "(id: 0, position: (20.0");
+        let physics_context =
+            Scenario::from_ron(&source).unwrap().build().unwrap();
+        let mut ids = physics_context
+            .bodies()
+            .map(|body| body.id().to_string())
+            .collect::<Vec<_>>();
+        ids.sort();
+        assert_eq!(ids, ["0", "1"]);
+    }
+
+    #[test]
+    fn builds_only_valid_scenarios() {
+        let mut scenario = Scenario::from_ron(SCENARIO).unwrap();
+        scenario.bodies[0].radius = f32::NAN;
+        let error = scenario.build().err().unwrap();
+        assert_eq!(error.to_string(), "bodies[0].radius: must be positive");
+
+        let mut scenario = Scenario::from_ron(SCENARIO).unwrap();
+        scenario.bodies[1].friction = f32::NAN;
+        let error = scenario.build().err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "bodies[1].friction: must be finite and not negative"
+        );
+
+        let mut scenario = Scenario::from_ron(SCENARIO).unwrap();
+        scenario.globals.gravity_amplifier = f32::INFINITY;
+        let error = scenario.build().err().unwrap();
+        assert!(error.to_string().starts_with("globals.gravity_amplifier:"));
+    }
+
+    #[test]
+    fn rejects_other_versions() {
+        let source = SCENARIO.replace("version: 1", "version: 2");
+        assert_eq!(
+            Scenario::from_ron(&source),
+            Err(ScenarioError::UnsupportedVersion(2))
+        );
+    }
+}
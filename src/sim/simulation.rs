@@ -1,110 +1,267 @@
-use std::ops::Mul;
+use std::f32::consts::PI;
 
-use glam::{Mat3, Quat, Vec2, Vec3, Vec3Swizzles};
+use glam::{Quat, Vec2, Vec3, Vec3Swizzles};
 use instant::Instant;
-use rapier2d::prelude::*;
-use winit::event::VirtualKeyCode;
 
 use crate::sim::physics::PhysicsContext;
-use crate::sim::State;
+use crate::sim::{
+    Action, CameraTarget, CollisionMode, DensityModel, Diagnostics,
+    DiagnosticsHistory, FixedTimestep, GravitySolver, Integration,
+    IntegratorKind, Scenario, ScenarioError, SimulationConfig, State, Trails,
+};
 
 // Universe
 pub const UNIVERSAL_GRAVITY: f32 = 0.000000000066743;
 pub const GRAVITY_AMPLIFIER: f32 = 40_000_000_000.0;
 pub const WORLD_RADIUS: f32 = 75.0;
 pub const WORLD_EDGE_SEGMENTS: u32 = 500;
+pub const WORLD_RESTITUTION: f32 = 0.6;
+pub const BARNES_HUT_THETA: f32 = 0.5;
+
+// Physics
+pub const PHYSICS_MAX_SUBSTEPS: u32 = 8;
+pub const TIME_SCALES: [f32; 10] =
+    [0.1, 0.2, 0.5, 1.0, 2.0, 5.0, 10.0, 20.0, 50.0, 100.0];
+pub const DIAGNOSTICS_HISTORY: usize = 600;
+pub const TRAIL_LENGTH: usize = 120;
+pub const FIELD_RESOLUTION: u32 = 96;
+pub const FIELD_MAX_RESOLUTION: u32 = 512;
+pub const FIELD_CONTOURS: u32 = 16;
 
 // Camera
 pub const CAM_ZOOM_SPEED: f32 = 5.0;
 pub const CAM_ROTATE_SPEED: f32 = 5.0;
 pub const CAM_PAN_SPEED: f32 = 400.0;
 pub const DAMPENING: f32 = 0.05;
+pub const CAM_WHEEL_ZOOM_STEP: f32 = 1.1;
+pub const CAM_PIXELS_PER_LINE: f32 = 100.0;
+pub const CAM_FOLLOW_RATE: f32 = 5.0;
+pub const CAM_CLUSTER_CELL: f32 = 10.0;
+
+// Slingshot
+pub const SLINGSHOT_STRENGTH: f32 = 1.0;
+pub const SLINGSHOT_DEFAULT_RADIUS: f32 = 0.5;
+pub const SLINGSHOT_MIN_RADIUS: f32 = 0.1;
+pub const SLINGSHOT_MAX_RADIUS: f32 = 5.0;
+pub const SLINGSHOT_RESIZE_STEP: f32 = 1.25;
+pub const SLINGSHOT_HEAVY_DENSITY: f32 = 10.0;
 
 // Bodies
+pub const DEFAULT_SEED: u64 = 0;
 pub const DEFAULT_NUM_BODIES: usize = 100;
-pub const BODY_MAX_RADIUS: f64 = 1.0;
-pub const BODY_MAX_ANG_VEL: f64 = 0.2 * (2.0 * std::f64::consts::PI);
-pub const BODY_MAX_LIN_VEL: f64 = 3.0;
+pub const BODY_MAX_RADIUS: f32 = 1.0;
+pub const BODY_MAX_ANG_VEL: f32 = 0.2 * (2.0 * PI);
+pub const BODY_MAX_LIN_VEL: f32 = 3.0;
 pub const BODY_RESTITUTION: f32 = 0.6;
 pub const BODY_FRICTION: f32 = 0.9;
+pub const COLOR_CLUSTER_CELL: f32 = 4.0;
+pub const BODY_SOFTENING_LENGTH: f32 = 0.5;
+pub const BODY_VELOCITY_DISPERSION: f32 = 0.05;
+pub const CENTRAL_BODY_MASS: f32 = 1000.0;
+pub const CENTRAL_BODY_RADIUS: f32 = 3.0;
 
 pub struct Simulation {
     pub state: State,
     pub physics_context: PhysicsContext,
+    pub timestep: FixedTimestep,
+    pub diagnostics: DiagnosticsHistory,
+    pub trails: Trails,
 }
 
 impl Default for Simulation {
     fn default() -> Self {
-        Simulation::new(DEFAULT_NUM_BODIES)
+        Simulation::new(SimulationConfig::default())
     }
 }
 
 impl Simulation {
-    pub fn new(num_bodies: usize) -> Self {
-        // Generate a bunch of bodies
-        let rngify = |x| (js_sys::Math::random() * x) as f32;
-
-        let mut physics_context = PhysicsContext::new();
-        for _ in 0..num_bodies {
-            // Calculate radius
-            let rotation = rngify(2.0 * std::f64::consts::PI);
-            let radius = rngify(BODY_MAX_RADIUS.sqrt());
-
-            // Calculate initial world position as polar coordinates
-            let r = WORLD_RADIUS * rngify(1.0).sqrt() - radius;
-            let theta = rngify(2.0 * std::f64::consts::PI);
-            let position = Mat3::from_rotation_z(theta) * Vec3::X.mul(r);
-
-            // Calculate initial velocity
-            let linvel_theta = rngify(2.0 * std::f64::consts::PI);
-            let magnitude = rngify(BODY_MAX_LIN_VEL);
-            let linvel =
-                Mat3::from_rotation_z(linvel_theta) * Vec3::X.mul(magnitude);
-
-            // Calculate initial angular velocity
-            let angvel = rngify(BODY_MAX_ANG_VEL);
-
-            // Bodies
-            let rigid_body = RigidBodyBuilder::new(RigidBodyType::Dynamic)
-                .translation(vector![position.x, position.y])
-                .linvel(vector![linvel.x, linvel.y])
-                .angvel(angvel)
-                .rotation(rotation)
-                .ccd_enabled(false) // Enable for higher accuracy
-                .build();
-            let collider = ColliderBuilder::ball(radius)
-                .restitution(BODY_RESTITUTION)
-                .friction(BODY_FRICTION)
-                .build();
-            physics_context.create_body(rigid_body, collider);
-        }
-
-        Self {
+    pub fn new(config: SimulationConfig) -> Self {
+        Self::from_scenario(&config.generate())
+            .expect("Generated scenarios are always valid")
+    }
+
+    pub fn from_scenario(scenario: &Scenario) -> Result<Self, ScenarioError> {
+        let physics_context = scenario.build()?;
+        let timestep = FixedTimestep::new(
+            physics_context.integration_parameters.dt,
+            PHYSICS_MAX_SUBSTEPS,
+        );
+
+        let mut diagnostics = DiagnosticsHistory::new(DIAGNOSTICS_HISTORY);
+        diagnostics.push(Diagnostics::measure(&physics_context));
+
+        Ok(Self {
             state: State::default(),
             physics_context,
+            timestep,
+            diagnostics,
+            trails: Trails::default(),
+        })
+    }
+
+    /// Advances physics by one fixed step and records trails.
+    pub fn step(&mut self) {
+        self.physics_context.step();
+        self.trails.record(&self.physics_context);
+    }
+
+    /// Measures the system as it is now and adds it to the history. This
+    /// sums over every body, so it runs once per frame rather than per step.
+    pub fn record_diagnostics(&mut self) {
+        self.diagnostics
+            .push(Diagnostics::measure(&self.physics_context));
+    }
+
+    /// Drops the selection and camera target once their body merged or left
+    /// the world.
+    pub fn forget_missing_bodies(&mut self) {
+        if let Some(id) = self.state.selected {
+            if self.physics_context.body(id).is_none() {
+                self.state.selected = None;
+            }
+        }
+        if let CameraTarget::Body(id) = self.state.camera_target {
+            if self.physics_context.body(id).is_none() {
+                self.state.camera_target = CameraTarget::Free;
+            }
         }
     }
 
     pub fn update(&mut self) {
+        // Update last frame, get delta time
+        let now = Instant::now();
+        let dt = (now - self.state.last_frame.unwrap_or(now)).as_secs_f32();
+        #[cfg(feature = "web")]
+        gloo_console::log!("frame-time: ", dt);
+        self.state.last_frame.replace(now);
+        self.state.wall_time += dt;
+
         // Check for pause key
         if self
             .state
             .input_controller
-            .is_key_pressed(VirtualKeyCode::Space)
+            .is_action_pressed(Action::TogglePause)
         {
             self.state.paused = !self.state.paused;
         }
 
-        // Step simulation
+        // Check for gravity solver key
+        if self
+            .state
+            .input_controller
+            .is_action_pressed(Action::ToggleBarnesHut)
+        {
+            let solver = &mut self.physics_context.gravity_solver;
+            *solver = match solver {
+                GravitySolver::BruteForce => GravitySolver::BarnesHut {
+                    theta: BARNES_HUT_THETA,
+                },
+                GravitySolver::BarnesHut { .. } => GravitySolver::BruteForce,
+            };
+            #[cfg(feature = "web")]
+            gloo_console::log!(format!("Gravity solver: {:?}", solver));
+        }
+
+        // Check for collision mode key
+        if self
+            .state
+            .input_controller
+            .is_action_pressed(Action::ToggleMerging)
+        {
+            let mode = &mut self.physics_context.collision_mode;
+            *mode = match mode {
+                CollisionMode::Bounce => CollisionMode::Merge {
+                    density: DensityModel::default(),
+                },
+                CollisionMode::Merge { .. } => CollisionMode::Bounce,
+            };
+            #[cfg(feature = "web")]
+            gloo_console::log!(format!("Collision mode: {:?}", mode));
+        }
+
+        // Check for integrator key
+        if self
+            .state
+            .input_controller
+            .is_action_pressed(Action::CycleIntegrator)
+        {
+            let integration = match self.physics_context.integration() {
+                Integration::Rapier => {
+                    Integration::Collisionless(IntegratorKind::Leapfrog)
+                }
+                Integration::Collisionless(IntegratorKind::Leapfrog) => {
+                    Integration::Collisionless(IntegratorKind::Yoshida4)
+                }
+                Integration::Collisionless(IntegratorKind::Yoshida4) => {
+                    Integration::Collisionless(IntegratorKind::RungeKutta4)
+                }
+                Integration::Collisionless(IntegratorKind::RungeKutta4) => {
+                    Integration::Rapier
+                }
+            };
+            self.physics_context.set_integration(integration);
+            #[cfg(feature = "web")]
+            gloo_console::log!(format!("Integration: {:?}", integration));
+        }
+
+        // Check for trails key
+        if self
+            .state
+            .input_controller
+            .is_action_pressed(Action::ToggleTrails)
+        {
+            self.trails.enabled = !self.trails.enabled;
+        }
+
+        // Check for vectors key
+        if self
+            .state
+            .input_controller
+            .is_action_pressed(Action::ToggleVectors)
+        {
+            self.state.show_vectors = !self.state.show_vectors;
+        }
+
+        // Check for world boundary key
+        if self
+            .state
+            .input_controller
+            .is_action_pressed(Action::CycleBoundary)
+        {
+            let boundary = self.physics_context.boundary().next();
+            self.physics_context.set_boundary(boundary);
+            #[cfg(feature = "web")]
+            gloo_console::log!(format!("World boundary: {:?}", boundary));
+        }
+
+        self.update_time_controls();
+        self.update_slingshot();
+        if self
+            .state
+            .input_controller
+            .is_action_pressed(Action::Deselect)
+        {
+            self.state.selected = None;
+        }
+
+        // Step simulation at a fixed rate, independent of the frame rate
         if !self.state.paused {
-            self.physics_context.step();
+            let steps = self.timestep.advance(dt * self.state.time_scale);
+            for _ in 0..steps {
+                self.step();
+            }
+            if steps > 0 {
+                self.record_diagnostics();
+            }
+        } else if self
+            .state
+            .input_controller
+            .is_action_pressed(Action::SingleStep)
+        {
+            self.single_step();
         }
 
-        // Update last frame, get delta time
-        let now = Instant::now();
-        let dt = (now - self.state.last_frame.unwrap_or(now)).as_secs_f32();
-        gloo_console::log!("frame-time: ", dt);
-        self.state.last_frame.replace(now);
+        self.forget_missing_bodies();
 
         // Control camera
         self.update_camera(dt);
@@ -113,38 +270,116 @@ impl Simulation {
         self.state.input_controller.update();
     }
 
+    /// Advances exactly one physics step and shows it without blending, for
+    /// stepping through a paused simulation.
+    pub fn single_step(&mut self) {
+        self.step();
+        self.record_diagnostics();
+        self.timestep.show_latest();
+    }
+
+    /// Sets how many simulated seconds pass per second, within the range of
+    /// `TIME_SCALES`. Faster runs may take more steps per frame.
+    pub fn set_time_scale(&mut self, time_scale: f32) {
+        let time_scale = time_scale
+            .clamp(TIME_SCALES[0], TIME_SCALES[TIME_SCALES.len() - 1]);
+        self.state.time_scale = time_scale;
+        self.timestep.max_substeps =
+            (PHYSICS_MAX_SUBSTEPS as f32 * time_scale.max(1.0)).ceil() as u32;
+    }
+
+    fn update_time_controls(&mut self) {
+        let input = &self.state.input_controller;
+        let time_scale = self.state.time_scale;
+        if input.is_action_pressed(Action::SpeedUp) {
+            if let Some(&faster) =
+                TIME_SCALES.iter().find(|&&scale| scale > time_scale)
+            {
+                self.set_time_scale(faster);
+            }
+        } else if input.is_action_pressed(Action::SlowDown) {
+            if let Some(&slower) =
+                TIME_SCALES.iter().rev().find(|&&scale| scale < time_scale)
+            {
+                self.set_time_scale(slower);
+            }
+        }
+
+        if self
+            .state
+            .input_controller
+            .is_action_pressed(Action::ToggleReverse)
+        {
+            let reversed = !self.physics_context.is_reversed();
+            if self.physics_context.set_reversed(reversed) != reversed {
+                #[cfg(feature = "web")]
+                gloo_console::log!(
+                    "Reverse time needs the leapfrog or Yoshida integrator"
+                );
+            }
+        }
+    }
+
+    // Drag with the left mouse button to launch new bodies, or click one to
+    // select it
+    fn update_slingshot(&mut self) {
+        let input = &self.state.input_controller;
+        let cursor = self.state.cursor_world();
+        let slingshot = &mut self.state.slingshot;
+
+        if input.is_action_pressed(Action::GrowBody) {
+            slingshot.resize(SLINGSHOT_RESIZE_STEP);
+        }
+        if input.is_action_pressed(Action::ShrinkBody) {
+            slingshot.resize(1.0 / SLINGSHOT_RESIZE_STEP);
+        }
+        slingshot.heavy = input.is_action_active(Action::HeavyBody);
+
+        if input.is_action_pressed(Action::Launch) {
+            // Clicking a body selects it instead
+            match self.physics_context.pick(cursor) {
+                Some(id) => self.state.selected = Some(id),
+                None => slingshot.grab(cursor),
+            }
+        } else if input.is_action_released(Action::Launch) {
+            if let Some(body) = slingshot.release(cursor) {
+                self.physics_context.spawn(&body);
+            }
+        }
+    }
+
     pub fn update_camera(&mut self, dt: f32) {
         // Handle input
         let state = &mut self.state;
         // Rotation
-        if state.input_controller.is_key_active(VirtualKeyCode::Left) {
+        if state.input_controller.is_action_active(Action::RotateLeft) {
             state.rotation += CAM_ROTATE_SPEED * dt;
         }
-        if state.input_controller.is_key_active(VirtualKeyCode::Right) {
+        if state.input_controller.is_action_active(Action::RotateRight) {
             state.rotation -= CAM_ROTATE_SPEED * dt;
         }
         // Scale
-        if state.input_controller.is_key_active(VirtualKeyCode::Up) {
+        if state.input_controller.is_action_active(Action::ZoomIn) {
             state.zoom += state.zoom * CAM_ZOOM_SPEED * dt;
         }
-        if state.input_controller.is_key_active(VirtualKeyCode::Down) {
+        if state.input_controller.is_action_active(Action::ZoomOut) {
             state.zoom -= state.zoom * CAM_ZOOM_SPEED * dt;
         }
         // Translation
         let mut cam_direction = Vec2::ZERO;
-        if state.input_controller.is_key_active(VirtualKeyCode::W) {
+        if state.input_controller.is_action_active(Action::PanUp) {
             cam_direction +=
                 (Quat::from_rotation_z(state.rotation) * (Vec3::Y)).xy();
         }
-        if state.input_controller.is_key_active(VirtualKeyCode::A) {
+        if state.input_controller.is_action_active(Action::PanLeft) {
             cam_direction -=
                 (Quat::from_rotation_z(state.rotation) * (Vec3::X)).xy();
         }
-        if state.input_controller.is_key_active(VirtualKeyCode::S) {
+        if state.input_controller.is_action_active(Action::PanDown) {
             cam_direction -=
                 (Quat::from_rotation_z(state.rotation) * (Vec3::Y)).xy();
         }
-        if state.input_controller.is_key_active(VirtualKeyCode::D) {
+        if state.input_controller.is_action_active(Action::PanRight) {
             cam_direction +=
                 (Quat::from_rotation_z(state.rotation) * (Vec3::X)).xy();
         }
@@ -152,13 +387,28 @@ impl Simulation {
         // Normalize
         cam_direction = cam_direction.normalize_or_zero();
 
+        // Follow mode
+        if state
+            .input_controller
+            .is_action_pressed(Action::CycleCameraTarget)
+        {
+            state.camera_target = state.camera_target.next(state.selected);
+            #[cfg(feature = "web")]
+            gloo_console::log!(format!(
+                "Camera target: {:?}",
+                state.camera_target
+            ));
+        }
+
         // Camera movement
-        if state.input_controller.is_one_of_key_active(vec![
-            VirtualKeyCode::W,
-            VirtualKeyCode::A,
-            VirtualKeyCode::S,
-            VirtualKeyCode::D,
+        if state.input_controller.is_one_of_action_active(vec![
+            Action::PanUp,
+            Action::PanLeft,
+            Action::PanDown,
+            Action::PanRight,
         ]) {
+            // Panning by hand stops following
+            state.camera_target = CameraTarget::Free;
             // Move camera
             state.pan_velocity = (cam_direction * CAM_PAN_SPEED) / state.zoom;
         } else if state.pan_velocity.length_squared() > 0.0 {
@@ -166,11 +416,50 @@ impl Simulation {
             state.pan_velocity += -1.0 * state.pan_velocity * DAMPENING;
         }
         // Wireframe
-        if state.input_controller.is_key_pressed(VirtualKeyCode::Q) {
+        if state
+            .input_controller
+            .is_action_pressed(Action::ToggleWireframe)
+        {
             state.wireframe = !state.wireframe;
         }
+        // Body colors
+        if state
+            .input_controller
+            .is_action_pressed(Action::CycleColorAttribute)
+        {
+            state.coloring.cycle_attribute();
+            #[cfg(feature = "web")]
+            gloo_console::log!(format!(
+                "Color by: {}",
+                state.coloring.attribute
+            ));
+        }
+        if state
+            .input_controller
+            .is_action_pressed(Action::CycleColormap)
+        {
+            state.coloring.colormap = state.coloring.colormap.next();
+            #[cfg(feature = "web")]
+            gloo_console::log!(format!(
+                "Colormap: {}",
+                state.coloring.colormap
+            ));
+        }
+        // Field overlay
+        if state.input_controller.is_action_pressed(Action::CycleField) {
+            state.field.cycle();
+        }
+        if state
+            .input_controller
+            .is_action_pressed(Action::ToggleContours)
+        {
+            state.field.contours = !state.field.contours;
+        }
         // Texture Change
-        if state.input_controller.is_key_released(VirtualKeyCode::E) {
+        if state
+            .input_controller
+            .is_action_released(Action::CycleTexture)
+        {
             state.rave = !state.rave;
             state.texture_key = match &state.texture_key as &str {
                 "rust" => "disco".to_owned(),
@@ -178,6 +467,156 @@ impl Simulation {
             };
         }
 
-        state.pan += state.pan_velocity * dt;
+        let alpha = self.timestep.alpha();
+        match state.camera_target.position(&self.physics_context, alpha) {
+            Some(target) => {
+                // Ease toward the target at the same rate at any frame rate
+                let t = 1.0 - (-CAM_FOLLOW_RATE * dt).exp();
+                state.pan = state.pan.lerp(target, t);
+                state.pan_velocity = Vec2::ZERO;
+            }
+            None => state.pan += state.pan_velocity * dt,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec2;
+
+    use crate::sim::{
+        Simulation, SimulationConfig, VelocityInit, CENTRAL_BODY_MASS,
+        PHYSICS_MAX_SUBSTEPS, WORLD_RADIUS,
+    };
+
+    fn positions(config: SimulationConfig) -> Vec<(f32, f32)> {
+        Simulation::new(config)
+            .physics_context
+            .bodies()
+            .map(|body| body.position().into())
+            .collect()
+    }
+
+    #[test]
+    fn same_seed_generates_same_bodies() {
+        let config = SimulationConfig {
+            seed: 42,
+            ..Default::default()
+        };
+        assert_eq!(positions(config.clone()), positions(config));
+    }
+
+    #[test]
+    fn different_seeds_generate_different_bodies() {
+        let a = positions(SimulationConfig {
+            seed: 1,
+            ..Default::default()
+        });
+        let b = positions(SimulationConfig {
+            seed: 2,
+            ..Default::default()
+        });
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn bodies_spawn_inside_world() {
+        let config = SimulationConfig {
+            num_bodies: 1000,
+            ..Default::default()
+        };
+        let count = positions(config.clone()).len();
+        // Plus the central body
+        assert_eq!(count, config.num_bodies + 1);
+        for (x, y) in positions(config) {
+            assert!((x * x + y * y).sqrt() <= WORLD_RADIUS);
+        }
+    }
+
+    #[test]
+    fn single_step_advances_one_step() {
+        let mut sim = Simulation::default();
+        sim.state.paused = true;
+        let dt = sim.physics_context.integration_parameters.dt;
+        let before = sim.diagnostics.len();
+        sim.single_step();
+        assert_eq!(sim.physics_context.time, dt);
+        assert_eq!(sim.diagnostics.len(), before + 1);
+        assert_eq!(sim.timestep.alpha(), 1.0);
+    }
+
+    #[test]
+    fn time_scale_stays_in_range() {
+        let mut sim = Simulation::default();
+        sim.set_time_scale(1000.0);
+        assert_eq!(sim.state.time_scale, 100.0);
+        assert_eq!(sim.timestep.max_substeps, 100 * PHYSICS_MAX_SUBSTEPS);
+        sim.set_time_scale(0.0);
+        assert_eq!(sim.state.time_scale, 0.1);
+        assert_eq!(sim.timestep.max_substeps, PHYSICS_MAX_SUBSTEPS);
+    }
+
+    #[test]
+    fn orbital_bodies_circle_the_center() {
+        let config = SimulationConfig {
+            velocity: VelocityInit::Orbital { dispersion: 0.0 },
+            ..Default::default()
+        };
+        let sim = Simulation::new(config);
+        let physics_context = &sim.physics_context;
+        let center = sim.diagnostics.latest().unwrap().center_of_mass;
+        // The central body comes first
+        for body in physics_context.bodies().skip(1) {
+            let rb = &physics_context.rigid_body_set[body.rigidbody_handle];
+            let velocity: Vec2 = (*rb.linvel()).into();
+            let offset = body.position() - center;
+            // Tangential, counterclockwise and fast enough to balance at
+            // least the central body's pull
+            assert!(
+                offset.normalize().dot(velocity).abs()
+                    < 1e-3 * velocity.length()
+            );
+            assert!(offset.perp_dot(velocity) > 0.0);
+            let central_pull = physics_context.gravitational_constant
+                * CENTRAL_BODY_MASS
+                / offset.length_squared();
+            let pull = velocity.length_squared() / offset.length();
+            assert!(pull >= 0.99 * central_pull, "{} < {}", pull, central_pull);
+        }
+    }
+
+    // Median distance of the bodies from the center after `steps`
+    fn spread(config: SimulationConfig, steps: usize) -> f32 {
+        let mut sim = Simulation::new(config);
+        for _ in 0..steps {
+            sim.step();
+        }
+        let mut distances = sim
+            .physics_context
+            .bodies()
+            .map(|body| body.position().length())
+            .collect::<Vec<_>>();
+        distances.sort_by(f32::total_cmp);
+        distances[distances.len() / 2]
+    }
+
+    #[test]
+    fn orbital_disc_keeps_its_size() {
+        let orbital = SimulationConfig::default();
+        let before = spread(orbital.clone(), 0);
+        let after = spread(orbital, 600);
+        assert!(
+            (after - before).abs() < 0.1 * before,
+            "{} -> {}",
+            before,
+            after
+        );
+
+        // Without orbits, the same bodies fall in
+        let random = SimulationConfig {
+            velocity: VelocityInit::Random,
+            ..Default::default()
+        };
+        assert!(spread(random, 600) < 0.8 * before);
     }
 }
@@ -0,0 +1,100 @@
+use glam::Vec2;
+
+use crate::sim::{
+    BodyDescriptor, SLINGSHOT_DEFAULT_RADIUS, SLINGSHOT_HEAVY_DENSITY,
+    SLINGSHOT_MAX_RADIUS, SLINGSHOT_MIN_RADIUS, SLINGSHOT_STRENGTH,
+};
+
+/// Places new bodies with the mouse: press where the body should go, pull
+/// back and release to launch it the opposite way.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Slingshot {
+    /// World position of the body being placed, while dragging
+    pub anchor: Option<Vec2>,
+    pub radius: f32,
+    /// Spawns bodies denser than usual, so heavier for their size
+    pub heavy: bool,
+}
+
+impl Default for Slingshot {
+    fn default() -> Self {
+        Self {
+            anchor: None,
+            radius: SLINGSHOT_DEFAULT_RADIUS,
+            heavy: false,
+        }
+    }
+}
+
+impl Slingshot {
+    pub fn grab(&mut self, cursor: Vec2) {
+        self.anchor = Some(cursor);
+    }
+
+    /// Ends the drag, describing the body to launch if one was placed.
+    pub fn release(&mut self, cursor: Vec2) -> Option<BodyDescriptor> {
+        let body = self.aim(cursor);
+        self.anchor = None;
+        body
+    }
+
+    /// The body that releasing at `cursor` would launch.
+    pub fn aim(&self, cursor: Vec2) -> Option<BodyDescriptor> {
+        let anchor = self.anchor?;
+        Some(BodyDescriptor {
+            velocity: (anchor - cursor) * SLINGSHOT_STRENGTH,
+            density: self.heavy.then_some(SLINGSHOT_HEAVY_DENSITY),
+            ..BodyDescriptor::new(anchor, self.radius)
+        })
+    }
+
+    /// Grows or shrinks the next body by `factor`, within limits.
+    pub fn resize(&mut self, factor: f32) {
+        self.radius = (self.radius * factor)
+            .clamp(SLINGSHOT_MIN_RADIUS, SLINGSHOT_MAX_RADIUS);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec2;
+
+    use super::Slingshot;
+    use crate::sim::{
+        SLINGSHOT_HEAVY_DENSITY, SLINGSHOT_MAX_RADIUS, SLINGSHOT_STRENGTH,
+    };
+
+    #[test]
+    fn launches_away_from_the_pull() {
+        let mut slingshot = Slingshot::default();
+        assert!(slingshot.release(Vec2::ZERO).is_none());
+
+        slingshot.grab(Vec2::new(10.0, 5.0));
+        let body = slingshot.release(Vec2::new(7.0, 9.0)).unwrap();
+        assert_eq!(body.position, Vec2::new(10.0, 5.0));
+        assert_eq!(body.velocity, Vec2::new(3.0, -4.0) * SLINGSHOT_STRENGTH);
+        assert_eq!(body.density, None);
+        assert!(slingshot.anchor.is_none());
+    }
+
+    #[test]
+    fn heavy_bodies_are_denser() {
+        let mut slingshot = Slingshot {
+            heavy: true,
+            ..Default::default()
+        };
+        slingshot.grab(Vec2::ZERO);
+        let body = slingshot.aim(Vec2::ZERO).unwrap();
+        assert_eq!(body.density, Some(SLINGSHOT_HEAVY_DENSITY));
+        assert_eq!(body.velocity, Vec2::ZERO);
+    }
+
+    #[test]
+    fn radius_stays_within_limits() {
+        let mut slingshot = Slingshot::default();
+        for _ in 0..100 {
+            slingshot.resize(1.5);
+        }
+        assert_eq!(slingshot.radius, SLINGSHOT_MAX_RADIUS);
+    }
+}
@@ -0,0 +1,302 @@
+use std::collections::HashMap;
+
+use glam::Vec2;
+use serde::{Deserialize, Serialize};
+
+use crate::sim::scenario::ron_options;
+use crate::sim::{
+    BodyDescriptor, BodyId, Globals, GravitySolver, Scenario, ScenarioError,
+    Simulation,
+};
+
+/// A complete, restorable picture of a running [`Simulation`].
+///
+/// Bodies are stored as a [`Scenario`] holding their current rigid-body
+/// state and ids, alongside the physics state a scenario doesn't describe
+/// and the camera.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub scenario: Scenario,
+    pub physics: PhysicsSnapshot,
+    pub camera: CameraSnapshot,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PhysicsSnapshot {
+    pub gravity_solver: GravitySolver,
+    pub time: f32,
+    pub reversed: bool,
+    pub next_body_id: BodyId,
+    /// Collisions so far, for bodies that had any
+    pub collisions: Vec<(BodyId, u32)>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CameraSnapshot {
+    pub pan: Vec2,
+    pub pan_velocity: Vec2,
+    pub rotation: f32,
+    pub zoom: f32,
+}
+
+impl Snapshot {
+    /// Compact binary encoding.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        bincode::serialize(self).expect("Snapshot is always serializable")
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ScenarioError> {
+        let snapshot: Self = bincode::deserialize(bytes)
+            .map_err(|e| ScenarioError::Parse(e.to_string()))?;
+        snapshot.scenario.validate()?;
+        Ok(snapshot)
+    }
+
+    /// Human-readable encoding.
+    pub fn to_ron(&self) -> String {
+        let config = ron::ser::PrettyConfig::default()
+            .extensions(ron::extensions::Extensions::IMPLICIT_SOME);
+        ron_options()
+            .to_string_pretty(self, config)
+            .expect("Snapshot is always serializable")
+    }
+
+    pub fn from_ron(source: &str) -> Result<Self, ScenarioError> {
+        let snapshot: Self = ron_options()
+            .from_str(source)
+            .map_err(|e| ScenarioError::Parse(e.to_string()))?;
+        snapshot.scenario.validate()?;
+        Ok(snapshot)
+    }
+}
+
+impl Simulation {
+    pub fn snapshot(&self) -> Snapshot {
+        let physics_context = &self.physics_context;
+        let bodies = physics_context
+            .bodies()
+            .map(|body| BodyDescriptor {
+                id: Some(body.id()),
+                ..BodyDescriptor::from_rigid_body(
+                    &physics_context.rigid_body_set[body.rigidbody_handle],
+                    &physics_context.collider_set[body.collider_handle],
+                )
+            })
+            .collect();
+        // The context only keeps the product of both gravity globals
+        let globals = Globals {
+            universal_gravity: physics_context.gravitational_constant,
+            gravity_amplifier: 1.0,
+            world_radius: physics_context.world_radius,
+            boundary: physics_context.boundary(),
+            collision_mode: physics_context.collision_mode,
+            softening: physics_context.softening,
+            integration: physics_context.integration(),
+            timestep: physics_context.integration_parameters.dt,
+        };
+
+        let collisions = physics_context
+            .bodies()
+            .filter(|body| body.collisions() > 0)
+            .map(|body| (body.id(), body.collisions()))
+            .collect();
+
+        Snapshot {
+            scenario: Scenario::new(globals, bodies),
+            physics: PhysicsSnapshot {
+                gravity_solver: physics_context.gravity_solver,
+                time: physics_context.time,
+                reversed: physics_context.is_reversed(),
+                next_body_id: physics_context.next_body_id(),
+                collisions,
+            },
+            camera: CameraSnapshot {
+                pan: self.state.pan,
+                pan_velocity: self.state.pan_velocity,
+                rotation: self.state.rotation,
+                zoom: self.state.zoom,
+            },
+        }
+    }
+
+    /// Replaces the bodies and camera, keeping input and view state. The
+    /// selection and camera target are kept only if their body is in the
+    /// snapshot. An invalid snapshot leaves the simulation as it was.
+    pub fn restore(
+        &mut self,
+        snapshot: &Snapshot,
+    ) -> Result<(), ScenarioError> {
+        let restored = Self::from_snapshot(snapshot)?;
+        self.physics_context = restored.physics_context;
+        self.timestep = restored.timestep;
+        self.diagnostics = restored.diagnostics;
+        self.trails.clear();
+        self.restore_camera(&snapshot.camera);
+        self.forget_missing_bodies();
+        Ok(())
+    }
+
+    pub fn from_snapshot(snapshot: &Snapshot) -> Result<Self, ScenarioError> {
+        let mut sim = Self::from_scenario(&snapshot.scenario)?;
+        sim.restore_physics(&snapshot.physics);
+        sim.restore_camera(&snapshot.camera);
+        Ok(sim)
+    }
+
+    fn restore_physics(&mut self, physics: &PhysicsSnapshot) {
+        let physics_context = &mut self.physics_context;
+        physics_context.gravity_solver = physics.gravity_solver;
+        physics_context.time = physics.time;
+        physics_context.set_reversed(physics.reversed);
+        physics_context.reserve_body_ids(physics.next_body_id);
+
+        let collisions = physics
+            .collisions
+            .iter()
+            .copied()
+            .collect::<HashMap<_, _>>();
+        for body in physics_context.bodies_mut() {
+            if let Some(&count) = collisions.get(&body.id()) {
+                body.add_collisions(count);
+            }
+        }
+    }
+
+    fn restore_camera(&mut self, camera: &CameraSnapshot) {
+        self.state.pan = camera.pan;
+        self.state.pan_velocity = camera.pan_velocity;
+        self.state.rotation = camera.rotation;
+        self.state.zoom = camera.zoom;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec2;
+
+    use crate::sim::{
+        BodyDescriptor, CameraTarget, Globals, GravitySolver, Integration,
+        IntegratorKind, Scenario, Simulation, Snapshot,
+    };
+
+    // Spread out enough that no contacts happen during the test
+    fn simulation() -> Simulation {
+        let bodies = (0..8)
+            .map(|i| {
+                let angle = i as f32 * std::f32::consts::TAU / 8.0;
+                let position = Vec2::from_angle(angle) * 40.0;
+                BodyDescriptor {
+                    velocity: position.perp() * 0.05,
+                    angular_velocity: i as f32 * 0.1,
+                    mass: Some(1.0 + i as f32),
+                    ..BodyDescriptor::new(position, 0.5 + 0.1 * i as f32)
+                }
+            })
+            .collect();
+        let mut sim = Simulation::from_scenario(&Scenario::new(
+            Globals::default(),
+            bodies,
+        ))
+        .unwrap();
+        sim.state.pan = Vec2::new(3.0, -2.0);
+        sim.state.zoom = 12.0;
+        sim
+    }
+
+    fn step(sim: &mut Simulation, steps: usize) -> Vec<(Vec2, f32)> {
+        for _ in 0..steps {
+            sim.physics_context.step();
+        }
+        sim.physics_context
+            .bodies()
+            .map(|body| (body.position(), body.rotation()))
+            .collect()
+    }
+
+    fn assert_same_trajectory(
+        original: &mut Simulation,
+        restored: &mut Simulation,
+    ) {
+        let expected = step(original, 120);
+        let actual = step(restored, 120);
+        assert_eq!(expected.len(), actual.len());
+        for ((p1, r1), (p2, r2)) in expected.into_iter().zip(actual) {
+            assert!(p1.distance(p2) < 1e-4, "{} != {}", p1, p2);
+            assert!((r1 - r2).abs() < 1e-4, "{} != {}", r1, r2);
+        }
+    }
+
+    #[test]
+    fn binary_round_trip_steps_the_same() {
+        let mut original = simulation();
+        step(&mut original, 60);
+
+        let bytes = original.snapshot().to_bytes();
+        let mut restored =
+            Simulation::from_snapshot(&Snapshot::from_bytes(&bytes).unwrap())
+                .unwrap();
+        assert_eq!(restored.state.pan, original.state.pan);
+        assert_eq!(restored.state.zoom, original.state.zoom);
+        assert_same_trajectory(&mut original, &mut restored);
+    }
+
+    #[test]
+    fn ron_round_trip_steps_the_same() {
+        let mut original = simulation();
+        step(&mut original, 60);
+
+        let ron = original.snapshot().to_ron();
+        let mut restored = Simulation::default();
+        restored
+            .restore(&Snapshot::from_ron(&ron).unwrap())
+            .unwrap();
+        assert_same_trajectory(&mut original, &mut restored);
+    }
+
+    #[test]
+    fn keeps_physics_state_and_ids() {
+        let mut original = simulation();
+        let physics_context = &mut original.physics_context;
+        physics_context.gravity_solver =
+            GravitySolver::BarnesHut { theta: 0.7 };
+        physics_context.set_integration(Integration::Collisionless(
+            IntegratorKind::Leapfrog,
+        ));
+        assert!(physics_context.set_reversed(true));
+        let first = physics_context.bodies().next().unwrap().id();
+        physics_context
+            .bodies_mut()
+            .next()
+            .unwrap()
+            .add_collisions(3);
+        let last = physics_context.bodies().last().unwrap().id();
+        assert!(physics_context.despawn(last));
+        step(&mut original, 10);
+
+        let mut restored = Simulation::default();
+        restored.state.selected = Some(first);
+        restored.state.camera_target = CameraTarget::Body(last);
+        restored
+            .restore(
+                &Snapshot::from_ron(&original.snapshot().to_ron()).unwrap(),
+            )
+            .unwrap();
+
+        let physics_context = &restored.physics_context;
+        assert_eq!(
+            physics_context.gravity_solver,
+            GravitySolver::BarnesHut { theta: 0.7 }
+        );
+        assert_eq!(physics_context.time, original.physics_context.time);
+        assert!(physics_context.is_reversed());
+        assert_eq!(physics_context.body(first).unwrap().collisions(), 3);
+        assert!(physics_context.body(last).is_none());
+        assert_eq!(
+            physics_context.next_body_id(),
+            original.physics_context.next_body_id()
+        );
+        // The selected body is still there, the followed one isn't
+        assert_eq!(restored.state.selected, Some(first));
+        assert_eq!(restored.state.camera_target, CameraTarget::Free);
+    }
+}
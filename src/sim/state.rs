@@ -1,23 +1,44 @@
+use std::collections::HashMap;
+
 use glam::{DVec2, DVec3, UVec2, Vec2};
 use instant::Instant;
-use winit::event::{ElementState, WindowEvent};
+use winit::event::{ElementState, MouseScrollDelta, TouchPhase, WindowEvent};
 
+use crate::render::Camera;
 use crate::sim::input::InputController;
+use crate::sim::{
+    Action, BodyId, CameraTarget, Coloring, FieldOverlay, Slingshot,
+    CAM_PIXELS_PER_LINE, CAM_WHEEL_ZOOM_STEP,
+};
 
 pub struct State {
     pub mouse_pos: DVec2,
     pub view_size: UVec2,
     pub last_frame: Option<Instant>,
     pub wireframe: bool,
+    /// Draw each body's velocity and acceleration
+    pub show_vectors: bool,
     pub paused: bool,
+    /// Simulated seconds per wall clock second
+    pub time_scale: f32,
+    /// Wall clock seconds since the start
+    pub wall_time: f32,
     pub bg_color: DVec3,
     pub texture_key: String,
     pub rave: bool,
+    pub coloring: Coloring,
+    pub field: FieldOverlay,
     pub pan: Vec2,
     pub pan_velocity: Vec2,
     pub rotation: f32,
     pub zoom: f32,
+    pub camera_target: CameraTarget,
     pub input_controller: InputController,
+    pub slingshot: Slingshot,
+    /// The body shown in the inspector
+    pub selected: Option<BodyId>,
+    /// Screen positions of fingers on the screen, by touch id
+    pub touches: HashMap<u64, Vec2>,
 }
 
 impl Default for State {
@@ -27,20 +48,85 @@ impl Default for State {
             view_size: UVec2::default(),
             last_frame: None,
             wireframe: false,
+            show_vectors: false,
             paused: false,
+            time_scale: 1.0,
+            wall_time: 0.0,
             bg_color: DVec3::default(),
             texture_key: "rust".to_owned(),
             rave: false,
+            coloring: Coloring::default(),
+            field: FieldOverlay::default(),
             pan: Vec2::ZERO,
             pan_velocity: Vec2::ZERO,
             rotation: 0.0,
             zoom: 100.0,
+            camera_target: CameraTarget::Free,
             input_controller: InputController::default(),
+            slingshot: Slingshot::default(),
+            selected: None,
+            touches: HashMap::new(),
         }
     }
 }
 
 impl State {
+    pub fn camera(&self) -> Camera {
+        Camera::new(
+            self.view_size.as_vec2(),
+            self.rotation,
+            self.pan,
+            self.zoom,
+        )
+    }
+
+    /// The world position under the mouse.
+    pub fn cursor_world(&self) -> Vec2 {
+        self.camera().screen_to_world(self.mouse_pos.as_vec2())
+    }
+
+    /// Scales the zoom by `factor`, keeping the world point under `screen`
+    /// in place.
+    pub fn zoom_at(&mut self, screen: Vec2, factor: f32) {
+        if self.view_size.min_element() == 0 {
+            self.zoom *= factor;
+            return;
+        }
+        let before = self.camera().screen_to_world(screen);
+        self.zoom *= factor;
+        let after = self.camera().screen_to_world(screen);
+        self.pan += before - after;
+    }
+
+    /// Moves the view so the world point under `from` ends up under `to`.
+    pub fn drag_pan(&mut self, from: Vec2, to: Vec2) {
+        if self.view_size.min_element() == 0 {
+            return;
+        }
+        let camera = self.camera();
+        self.pan += camera.screen_to_world(from) - camera.screen_to_world(to);
+        self.camera_target = CameraTarget::Free;
+    }
+
+    // Two fingers pan with their midpoint and zoom with their spread
+    fn touch_moved(&mut self, id: u64, position: Vec2) {
+        let Some(previous) = self.touches.insert(id, position) else {
+            return;
+        };
+        if self.touches.len() != 2 {
+            return;
+        }
+        let other =
+            *self.touches.iter().find(|(key, _)| **key != id).unwrap().1;
+        let (old_mid, new_mid) =
+            ((previous + other) / 2.0, (position + other) / 2.0);
+        let old_spread = previous.distance(other);
+        self.drag_pan(old_mid, new_mid);
+        if old_spread > 0.0 {
+            self.zoom_at(new_mid, position.distance(other) / old_spread);
+        }
+    }
+
     pub fn handle_input(&mut self, event: &WindowEvent) {
         // We have no events to handle currently
         match event {
@@ -56,8 +142,46 @@ impl State {
                     }
                 }
             }
+            WindowEvent::MouseInput { state, button, .. } => match state {
+                ElementState::Pressed => {
+                    self.input_controller.press_button(*button)
+                }
+                ElementState::Released => {
+                    self.input_controller.release_button(*button)
+                }
+            },
             WindowEvent::CursorMoved { position, .. } => {
-                self.mouse_pos = DVec2::new(position.x, position.y);
+                let position = DVec2::new(position.x, position.y);
+                // Middle or right drag pans the view
+                if self.input_controller.is_action_active(Action::DragPan) {
+                    self.drag_pan(self.mouse_pos.as_vec2(), position.as_vec2());
+                }
+                self.mouse_pos = position;
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                let lines = match delta {
+                    MouseScrollDelta::LineDelta(_, y) => *y,
+                    MouseScrollDelta::PixelDelta(position) => {
+                        position.y as f32 / CAM_PIXELS_PER_LINE
+                    }
+                };
+                self.zoom_at(
+                    self.mouse_pos.as_vec2(),
+                    CAM_WHEEL_ZOOM_STEP.powf(lines),
+                );
+            }
+            WindowEvent::Touch(touch) => {
+                let position =
+                    Vec2::new(touch.location.x as f32, touch.location.y as f32);
+                match touch.phase {
+                    TouchPhase::Started => {
+                        self.touches.insert(touch.id, position);
+                    }
+                    TouchPhase::Moved => self.touch_moved(touch.id, position),
+                    TouchPhase::Ended | TouchPhase::Cancelled => {
+                        self.touches.remove(&touch.id);
+                    }
+                }
             }
             WindowEvent::Resized(size) => {
                 self.view_size = UVec2::new(size.width, size.height);
@@ -66,3 +190,55 @@ impl State {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use glam::{UVec2, Vec2};
+
+    use super::State;
+
+    fn state() -> State {
+        State {
+            view_size: UVec2::new(800, 600),
+            rotation: 0.4,
+            pan: Vec2::new(5.0, -2.0),
+            zoom: 20.0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn zoom_keeps_the_cursor_point_still() {
+        let mut state = state();
+        let cursor = Vec2::new(650.0, 120.0);
+        let before = state.camera().screen_to_world(cursor);
+        state.zoom_at(cursor, 1.5);
+        let after = state.camera().screen_to_world(cursor);
+        assert_eq!(state.zoom, 30.0);
+        assert!(before.distance(after) < 1e-4, "{} != {}", before, after);
+    }
+
+    #[test]
+    fn drag_moves_the_world_with_the_cursor() {
+        let mut state = state();
+        let (from, to) = (Vec2::new(100.0, 100.0), Vec2::new(300.0, 250.0));
+        let grabbed = state.camera().screen_to_world(from);
+        state.drag_pan(from, to);
+        let under = state.camera().screen_to_world(to);
+        assert!(grabbed.distance(under) < 1e-4, "{} != {}", grabbed, under);
+    }
+
+    #[test]
+    fn pinch_zooms_about_the_fingers() {
+        let mut state = state();
+        state.touches.insert(0, Vec2::new(300.0, 300.0));
+        state.touches.insert(1, Vec2::new(500.0, 300.0));
+        let anchor = state.camera().screen_to_world(Vec2::new(300.0, 300.0));
+        // Spread the fingers to twice the distance
+        state.touch_moved(1, Vec2::new(700.0, 300.0));
+        assert!((state.zoom - 40.0).abs() < 1e-4);
+        // The still finger keeps holding the same world point
+        let held = state.camera().screen_to_world(Vec2::new(300.0, 300.0));
+        assert!(anchor.distance(held) < 1e-4, "{} != {}", anchor, held);
+    }
+}
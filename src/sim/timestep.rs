@@ -0,0 +1,97 @@
+/// Accumulates wall-clock frame time and hands it out in fixed physics steps.
+pub struct FixedTimestep {
+    pub dt: f32,
+    pub max_substeps: u32,
+    accumulator: f32,
+}
+
+impl FixedTimestep {
+    pub fn new(dt: f32, max_substeps: u32) -> Self {
+        Self {
+            dt,
+            max_substeps,
+            accumulator: 0.0,
+        }
+    }
+
+    /// Adds `frame_dt` seconds and returns how many fixed steps to run.
+    pub fn advance(&mut self, frame_dt: f32) -> u32 {
+        self.accumulator += frame_dt.max(0.0);
+
+        let steps =
+            ((self.accumulator / self.dt) as u32).min(self.max_substeps);
+        self.accumulator -= steps as f32 * self.dt;
+
+        // Drop the backlog we can't catch up on, rather than spiralling into
+        // longer and longer frames
+        if steps == self.max_substeps {
+            self.accumulator = self.accumulator.min(self.dt);
+        }
+        steps
+    }
+
+    /// Drops the partial step, so the latest step is drawn as is rather than
+    /// blended with the one before.
+    pub fn show_latest(&mut self) {
+        self.accumulator = self.dt;
+    }
+
+    /// How far we are between the last step and the next one, in `[0, 1]`.
+    pub fn alpha(&self) -> f32 {
+        (self.accumulator / self.dt).clamp(0.0, 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FixedTimestep;
+
+    // Powers of two keep the arithmetic exact
+    const DT: f32 = 0.25;
+
+    #[test]
+    fn steps_whole_steps() {
+        let mut timestep = FixedTimestep::new(DT, 8);
+        assert_eq!(timestep.advance(0.5), 2);
+        assert_eq!(timestep.alpha(), 0.0);
+        assert_eq!(timestep.advance(0.0), 0);
+        assert_eq!(timestep.advance(-1.0), 0);
+    }
+
+    #[test]
+    fn carries_the_remainder() {
+        let mut timestep = FixedTimestep::new(DT, 8);
+        assert_eq!(timestep.advance(0.125), 0);
+        assert_eq!(timestep.alpha(), 0.5);
+        assert_eq!(timestep.advance(0.1875), 1);
+        assert_eq!(timestep.alpha(), 0.25);
+        assert_eq!(timestep.advance(0.1875), 1);
+        assert_eq!(timestep.alpha(), 0.0);
+    }
+
+    #[test]
+    fn drops_the_backlog_past_max_substeps() {
+        let mut timestep = FixedTimestep::new(DT, 4);
+        assert_eq!(timestep.advance(10.0), 4);
+        // At most one step is left over for the next frame
+        assert_eq!(timestep.alpha(), 1.0);
+        assert_eq!(timestep.advance(0.0), 1);
+        assert_eq!(timestep.advance(0.0), 0);
+
+        // Reaching the limit exactly keeps the partial step
+        let mut timestep = FixedTimestep::new(DT, 4);
+        assert_eq!(timestep.advance(1.125), 4);
+        assert_eq!(timestep.alpha(), 0.5);
+    }
+
+    #[test]
+    fn shows_the_latest_step() {
+        let mut timestep = FixedTimestep::new(DT, 8);
+        timestep.advance(0.125);
+        timestep.show_latest();
+        assert_eq!(timestep.alpha(), 1.0);
+        // The dropped partial step is made up right away
+        assert_eq!(timestep.advance(0.0), 1);
+        assert_eq!(timestep.alpha(), 0.0);
+    }
+}
@@ -0,0 +1,169 @@
+use std::collections::{HashMap, VecDeque};
+
+use glam::Vec2;
+
+use crate::sim::physics::PhysicsContext;
+use crate::sim::{BodyId, TRAIL_LENGTH};
+
+/// Where each body has been over its last few steps.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Trails {
+    pub enabled: bool,
+    /// Positions kept per body
+    length: usize,
+    trails: HashMap<BodyId, VecDeque<Vec2>>,
+}
+
+impl Default for Trails {
+    fn default() -> Self {
+        Self::new(TRAIL_LENGTH)
+    }
+}
+
+impl Trails {
+    pub fn new(length: usize) -> Self {
+        Self {
+            enabled: true,
+            length,
+            trails: HashMap::new(),
+        }
+    }
+
+    pub fn length(&self) -> usize {
+        self.length
+    }
+
+    /// Changes how many positions are kept, dropping the oldest if shorter.
+    pub fn set_length(&mut self, length: usize) {
+        self.length = length;
+        for trail in self.trails.values_mut() {
+            while trail.len() > length {
+                trail.pop_front();
+            }
+        }
+    }
+
+    /// Adds every body's current position, and forgets bodies that are gone.
+    pub fn record(&mut self, physics_context: &PhysicsContext) {
+        if !self.enabled || self.length == 0 {
+            self.trails.clear();
+            return;
+        }
+
+        let mut recorded = HashMap::with_capacity(self.trails.len());
+        for body in physics_context.bodies() {
+            let position = body.position();
+            let mut trail = self.trails.remove(&body.id()).unwrap_or_default();
+            // Start over after wrapping around the world, rather than
+            // streaking across it
+            if trail.back().is_some_and(|last| {
+                last.distance(position) > physics_context.world_radius
+            }) {
+                trail.clear();
+            }
+            if trail.len() == self.length {
+                trail.pop_front();
+            }
+            trail.push_back(position);
+            recorded.insert(body.id(), trail);
+        }
+        self.trails = recorded;
+    }
+
+    pub fn clear(&mut self) {
+        self.trails.clear();
+    }
+
+    /// The trail of `id`, oldest position first.
+    pub fn get(&self, id: BodyId) -> Option<&VecDeque<Vec2>> {
+        self.trails.get(&id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&BodyId, &VecDeque<Vec2>)> {
+        self.trails.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec2;
+
+    use super::Trails;
+    use crate::sim::physics::PhysicsContext;
+    use crate::sim::{BodyDescriptor, BoundaryPolicy};
+
+    fn moving(velocity: Vec2) -> BodyDescriptor {
+        BodyDescriptor {
+            velocity,
+            ..BodyDescriptor::new(Vec2::ZERO, 0.5)
+        }
+    }
+
+    #[test]
+    fn keeps_the_latest_positions() {
+        let mut physics_context = PhysicsContext::new();
+        physics_context.gravitational_constant = 0.0;
+        let id = physics_context.spawn(&moving(Vec2::new(6.0, 0.0)));
+        let mut trails = Trails::new(5);
+        for _ in 0..8 {
+            physics_context.step();
+            trails.record(&physics_context);
+        }
+
+        let trail = trails.get(id).unwrap();
+        assert_eq!(trail.len(), 5);
+        assert_eq!(
+            *trail.back().unwrap(),
+            physics_context.body(id).unwrap().position()
+        );
+        assert!(trail
+            .iter()
+            .zip(trail.iter().skip(1))
+            .all(|(a, b)| a.x < b.x));
+
+        trails.set_length(2);
+        assert_eq!(trails.get(id).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn forgets_removed_bodies() {
+        let mut physics_context = PhysicsContext::new();
+        let a = physics_context.spawn(&moving(Vec2::X));
+        let b = physics_context.spawn(&moving(Vec2::Y));
+        let mut trails = Trails::default();
+        trails.record(&physics_context);
+        assert_eq!(trails.iter().count(), 2);
+
+        physics_context.despawn(a);
+        trails.record(&physics_context);
+        assert!(trails.get(a).is_none());
+        assert_eq!(trails.get(b).unwrap().len(), 2);
+
+        trails.enabled = false;
+        trails.record(&physics_context);
+        assert_eq!(trails.iter().count(), 0);
+    }
+
+    #[test]
+    fn restarts_after_wrapping() {
+        let mut physics_context = PhysicsContext::new();
+        physics_context.gravitational_constant = 0.0;
+        physics_context.set_boundary(BoundaryPolicy::Wrap);
+        let radius = physics_context.world_radius;
+        let id = physics_context.spawn(&BodyDescriptor {
+            velocity: Vec2::new(60.0, 0.0),
+            ..BodyDescriptor::new(Vec2::new(radius - 0.5, 0.0), 0.5)
+        });
+        let mut trails = Trails::default();
+        trails.record(&physics_context);
+        for _ in 0..3 {
+            physics_context.step();
+            trails.record(&physics_context);
+        }
+
+        // Only positions from after the jump are left
+        let trail = trails.get(id).unwrap();
+        assert!(trail.len() < 4);
+        assert!(trail.iter().all(|position| position.x < 0.0), "{:?}", trail);
+    }
+}